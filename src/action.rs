@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+use ron::{value::Map, Value};
+use serde::{Deserialize, Deserializer};
+
+/// Something the player can do from the keyboard, whichever key it is
+/// bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Launch,
+    Pause,
+    Restart,
+    Quit,
+}
+
+impl Action {
+    /// Every action, in the order the controls menu lists them.
+    pub const ALL: [Action; 6] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Launch,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+    ];
+
+    /// Name of the action as shown to the player.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Launch => "Launch",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::Quit => "Quit",
+        }
+    }
+
+    /// Name of the action's setting in the config file.
+    pub fn setting(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Launch => "launch",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+        }
+    }
+}
+
+/// The key bound to every [`Action`]. Keys are written in the config
+/// file by their `KeyCode` name, such as `"Space"` or `"A"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyBindings {
+    #[serde(deserialize_with = "deserialize_key")]
+    pub move_left: KeyCode,
+    #[serde(deserialize_with = "deserialize_key")]
+    pub move_right: KeyCode,
+    #[serde(deserialize_with = "deserialize_key")]
+    pub launch: KeyCode,
+    #[serde(deserialize_with = "deserialize_key")]
+    pub pause: KeyCode,
+    #[serde(deserialize_with = "deserialize_key")]
+    pub restart: KeyCode,
+    #[serde(deserialize_with = "deserialize_key")]
+    pub quit: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            move_left: KeyCode::Left,
+            move_right: KeyCode::Right,
+            launch: KeyCode::Space,
+            pause: KeyCode::Escape,
+            restart: KeyCode::R,
+            quit: KeyCode::Q,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::MoveLeft => self.move_left,
+            Action::MoveRight => self.move_right,
+            Action::Launch => self.launch,
+            Action::Pause => self.pause,
+            Action::Restart => self.restart,
+            Action::Quit => self.quit,
+        }
+    }
+
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        let binding = match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Launch => &mut self.launch,
+            Action::Pause => &mut self.pause,
+            Action::Restart => &mut self.restart,
+            Action::Quit => &mut self.quit,
+        };
+        *binding = key;
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.key(action) == key)
+    }
+
+    /// The first two actions bound to the same key, if any.
+    pub fn conflict(&self) -> Option<(Action, Action)> {
+        Action::ALL.into_iter().enumerate().find_map(
+            |(i, first)| {
+                Action::ALL[i + 1..]
+                    .iter()
+                    .find(|&&second| {
+                        self.key(first) == self.key(second)
+                    })
+                    .map(|&second| (first, second))
+            },
+        )
+    }
+
+    pub fn pressed(
+        &self,
+        input: &Input<KeyCode>,
+        action: Action,
+    ) -> bool {
+        input.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(
+        &self,
+        input: &Input<KeyCode>,
+        action: Action,
+    ) -> bool {
+        input.just_pressed(self.key(action))
+    }
+
+    /// Name of the key bound to `action`, as shown to the player and
+    /// written in the config file.
+    pub fn key_name(&self, action: Action) -> String {
+        format!("{:?}", self.key(action))
+    }
+
+    /// The bindings as they are written in the config file.
+    pub fn to_value(&self) -> Value {
+        let mut bindings = Map::new();
+        for action in Action::ALL {
+            bindings.insert(
+                Value::String(action.setting().to_owned()),
+                Value::String(self.key_name(action)),
+            );
+        }
+        Value::Map(bindings)
+    }
+}
+
+/// Reads a key written by its `KeyCode` name. Names are strings, as
+/// RON reads a bare word as a unit struct rather than an enum.
+fn deserialize_key<'de, D>(
+    deserializer: D,
+) -> Result<KeyCode, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    ron::from_str(&name).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "`{name}` is not a key"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigError, GameConfig};
+
+    #[test]
+    fn default_bindings_have_no_conflict() {
+        assert_eq!(KeyBindings::default().conflict(), None);
+    }
+
+    #[test]
+    fn binding_a_key_in_use_conflicts() {
+        let mut bindings = KeyBindings::default();
+        bindings.set(Action::Quit, KeyCode::Space);
+        assert_eq!(
+            bindings.conflict(),
+            Some((Action::Launch, Action::Quit))
+        );
+        // The first action in menu order wins the key
+        assert_eq!(
+            bindings.action(KeyCode::Space),
+            Some(Action::Launch)
+        );
+    }
+
+    #[test]
+    fn rebinding_an_action_to_its_own_key_does_not_conflict() {
+        let mut bindings = KeyBindings::default();
+        for action in Action::ALL {
+            let key = bindings.key(action);
+            assert_eq!(bindings.action(key), Some(action));
+            bindings.set(action, key);
+        }
+        assert_eq!(bindings.conflict(), None);
+        assert_eq!(bindings, KeyBindings::default());
+    }
+
+    #[test]
+    fn bindings_read_back_as_written() {
+        let mut bindings = KeyBindings::default();
+        bindings.set(Action::MoveLeft, KeyCode::A);
+        bindings.set(Action::MoveRight, KeyCode::D);
+        let read: KeyBindings =
+            bindings.to_value().into_rust().unwrap();
+        assert_eq!(read, bindings);
+    }
+
+    #[test]
+    fn config_with_conflicting_bindings_is_rejected() {
+        let error = GameConfig::from_settings(
+            Map::new(),
+            &[(
+                "bindings".to_owned(),
+                r#"(launch: "Left")"#.to_owned(),
+            )],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigError::ConflictingBindings {
+                key: KeyCode::Left,
+                first: "Move left",
+                second: "Launch",
+            }
+        ));
+    }
+}
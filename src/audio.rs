@@ -0,0 +1,405 @@
+use bevy::{
+    asset::LoadState, audio::Volume, prelude::*, utils::HashMap,
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    components::{brick::BrickHit, paddle::Paddle},
+    config::GameConfig,
+    game::{GameplaySet, LevelClearedEvent, LifeLostEvent},
+    physics::{ColliderKind, CollisionEvent},
+    powerup::PowerUpCollectedEvent,
+};
+
+pub const COLLISION_SOUND: &str =
+    "sounds/breakout_collision.ogg";
+pub const MAX_VOICES: usize = 8;
+// How far sounds are panned towards the side they come from
+pub const STEREO_WIDTH: f32 = 0.6;
+
+/// Plays a sound effect for everything that happens in play, from
+/// the sound sets in the config.
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_sounds)
+            .add_systems(
+                Update,
+                load_sounds
+                    .run_if(resource_changed::<GameConfig>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_sounds.after(GameplaySet),
+            );
+    }
+}
+
+/// Something that makes a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundKind {
+    Wall,
+    Paddle,
+    BrickDamaged,
+    BrickDestroyed,
+    IndestructibleBrick,
+    /// A brick whose type has a sound set of its own, by the type's
+    /// symbol in level grids.
+    Brick(char),
+    /// Two balls bouncing off each other.
+    Ball,
+    BallLost,
+    PowerUp,
+    LevelClear,
+}
+
+impl SoundKind {
+    /// The kinds that every config has a sound set for.
+    pub const ALL: [SoundKind; 9] = [
+        SoundKind::Wall,
+        SoundKind::Paddle,
+        SoundKind::BrickDamaged,
+        SoundKind::BrickDestroyed,
+        SoundKind::IndestructibleBrick,
+        SoundKind::Ball,
+        SoundKind::BallLost,
+        SoundKind::PowerUp,
+        SoundKind::LevelClear,
+    ];
+
+    /// The sound a ball makes running into `kind`. Bricks without a
+    /// sound set for their type fall back to the set for what the
+    /// hit did to them.
+    pub fn of_collision(
+        kind: ColliderKind,
+        config: &SoundConfig,
+    ) -> Option<SoundKind> {
+        match kind {
+            ColliderKind::Wall(_) => Some(SoundKind::Wall),
+            ColliderKind::Paddle => Some(SoundKind::Paddle),
+            ColliderKind::Brick { brick_type, hit } => {
+                let symbol = brick_type.symbol;
+                if config.bricks.contains_key(&symbol) {
+                    return Some(SoundKind::Brick(symbol));
+                }
+                Some(match hit {
+                    BrickHit::Damaged => SoundKind::BrickDamaged,
+                    BrickHit::Destroyed => {
+                        SoundKind::BrickDestroyed
+                    }
+                    BrickHit::Deflected => {
+                        SoundKind::IndestructibleBrick
+                    }
+                })
+            }
+            ColliderKind::Ball => Some(SoundKind::Ball),
+            ColliderKind::Other => None,
+        }
+    }
+
+    /// Name of the kind's sound set in the config file.
+    pub fn setting(self) -> String {
+        let name = match self {
+            SoundKind::Wall => "wall",
+            SoundKind::Paddle => "paddle",
+            SoundKind::BrickDamaged => "brick_damaged",
+            SoundKind::BrickDestroyed => "brick_destroyed",
+            SoundKind::IndestructibleBrick => {
+                "indestructible_brick"
+            }
+            SoundKind::Ball => "ball",
+            SoundKind::BallLost => "ball_lost",
+            SoundKind::PowerUp => "power_up",
+            SoundKind::LevelClear => "level_clear",
+            SoundKind::Brick(symbol) => {
+                return format!("bricks '{symbol}'");
+            }
+        };
+        name.to_owned()
+    }
+}
+
+/// Sounds to pick from for one [`SoundKind`], and how to vary them
+/// so that repeats don't all sound the same. Settings left out of a
+/// set in the config file keep the values of `SoundSet::default`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundSet {
+    /// Files in the assets folder, one of which is picked at random
+    /// each time. No files silences the set.
+    pub files: Vec<String>,
+    pub volume: f32,
+    /// Largest fraction the volume is raised or lowered by.
+    pub volume_variation: f32,
+    /// Playback speed, which raises the pitch above 1.0.
+    pub pitch: f32,
+    /// Largest fraction the pitch is raised or lowered by.
+    pub pitch_variation: f32,
+}
+
+impl Default for SoundSet {
+    fn default() -> Self {
+        SoundSet {
+            files: vec![COLLISION_SOUND.to_owned()],
+            volume: 1.0,
+            volume_variation: 0.1,
+            pitch: 1.0,
+            pitch_variation: 0.05,
+        }
+    }
+}
+
+impl SoundSet {
+    fn with(volume: f32, pitch: f32) -> Self {
+        SoundSet {
+            volume,
+            pitch,
+            ..default()
+        }
+    }
+
+    /// The first setting out of range, if any.
+    pub fn invalid_setting(&self) -> Option<&'static str> {
+        let fraction = 0.0..1.0;
+        if self.volume.is_nan() || self.volume < 0.0 {
+            Some("volume")
+        } else if !fraction.contains(&self.volume_variation) {
+            Some("volume_variation")
+        } else if self.pitch.is_nan() || self.pitch <= 0.0 {
+            Some("pitch")
+        } else if !fraction.contains(&self.pitch_variation) {
+            Some("pitch_variation")
+        } else {
+            None
+        }
+    }
+}
+
+/// Every sound set, and how they are mixed.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundConfig {
+    /// Most sound effects playing at once. Any more are dropped, so
+    /// that bursts of hits don't clip.
+    pub max_voices: usize,
+    /// How far sounds are panned towards the side of the arena they
+    /// come from, from 0.0 for none to 1.0.
+    pub stereo_width: f32,
+    pub wall: SoundSet,
+    pub paddle: SoundSet,
+    pub brick_damaged: SoundSet,
+    pub brick_destroyed: SoundSet,
+    pub indestructible_brick: SoundSet,
+    /// Sound sets of their own for types of brick, by their symbol
+    /// in level grids, played whatever the hit did to the brick.
+    /// Other bricks use the three sets above.
+    pub bricks: HashMap<char, SoundSet>,
+    pub ball: SoundSet,
+    pub ball_lost: SoundSet,
+    pub power_up: SoundSet,
+    pub level_clear: SoundSet,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        SoundConfig {
+            max_voices: MAX_VOICES,
+            stereo_width: STEREO_WIDTH,
+            wall: SoundSet::with(0.6, 1.0),
+            paddle: SoundSet::with(0.8, 0.8),
+            brick_damaged: SoundSet::with(0.8, 1.1),
+            brick_destroyed: SoundSet::with(1.0, 1.25),
+            indestructible_brick: SoundSet::with(0.7, 0.6),
+            bricks: HashMap::default(),
+            ball: SoundSet::with(0.5, 1.5),
+            ball_lost: SoundSet::with(1.0, 0.5),
+            power_up: SoundSet::with(1.0, 1.6),
+            level_clear: SoundSet::with(1.0, 0.75),
+        }
+    }
+}
+
+impl SoundConfig {
+    /// The sound set for `kind`, which only a brick type left out of
+    /// `bricks` has none of.
+    pub fn set(&self, kind: SoundKind) -> Option<&SoundSet> {
+        Some(match kind {
+            SoundKind::Wall => &self.wall,
+            SoundKind::Paddle => &self.paddle,
+            SoundKind::BrickDamaged => &self.brick_damaged,
+            SoundKind::BrickDestroyed => &self.brick_destroyed,
+            SoundKind::IndestructibleBrick => {
+                &self.indestructible_brick
+            }
+            SoundKind::Brick(symbol) => {
+                return self.bricks.get(&symbol);
+            }
+            SoundKind::Ball => &self.ball,
+            SoundKind::BallLost => &self.ball_lost,
+            SoundKind::PowerUp => &self.power_up,
+            SoundKind::LevelClear => &self.level_clear,
+        })
+    }
+
+    /// Every kind the config has a sound set for.
+    pub fn kinds(&self) -> impl Iterator<Item = SoundKind> + '_ {
+        SoundKind::ALL.into_iter().chain(
+            self.bricks
+                .keys()
+                .map(|&symbol| SoundKind::Brick(symbol)),
+        )
+    }
+
+    /// The first sound set with a setting out of range, along with
+    /// that setting, if any.
+    pub fn invalid_setting(
+        &self,
+    ) -> Option<(SoundKind, &'static str)> {
+        self.kinds().find_map(|kind| {
+            self.set(kind)?
+                .invalid_setting()
+                .map(|setting| (kind, setting))
+        })
+    }
+}
+
+/// The loaded files of every sound set.
+#[derive(Resource, Default)]
+pub struct Sounds(
+    pub HashMap<SoundKind, Vec<Handle<AudioSource>>>,
+);
+
+/// Marks a sound effect while it plays, to count the voices in use.
+#[derive(Component)]
+pub struct SoundEffect;
+
+pub fn load_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
+    let sounds = config
+        .sounds
+        .kinds()
+        .filter_map(|kind| {
+            let handles = config
+                .sounds
+                .set(kind)?
+                .files
+                .iter()
+                .map(|file| asset_server.load(file.as_str()))
+                .collect();
+            Some((kind, handles))
+        })
+        .collect();
+    commands.insert_resource(Sounds(sounds));
+}
+
+/// Plays a sound for each kind of thing that happened this step,
+/// panned towards where it happened, as long as there are voices
+/// left.
+#[allow(clippy::too_many_arguments)]
+pub fn play_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut life_lost_events: EventReader<LifeLostEvent>,
+    mut collected_events: EventReader<PowerUpCollectedEvent>,
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    sounds: Option<Res<Sounds>>,
+    config: Res<GameConfig>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    voice_query: Query<(), With<SoundEffect>>,
+) {
+    // Sounds of the same kind within a step would only be heard as
+    // one louder sound
+    let mut to_play: Vec<(SoundKind, f32)> = Vec::new();
+    let mut queue = |kind: SoundKind, x: f32| {
+        if !to_play.iter().any(|&(queued, _)| queued == kind) {
+            to_play.push((kind, x));
+        }
+    };
+    for event in collision_events.iter() {
+        if let Some(kind) =
+            SoundKind::of_collision(event.kind, &config.sounds)
+        {
+            queue(kind, event.point.x);
+        }
+    }
+    let paddle_x = paddle_query
+        .get_single()
+        .map_or(0.0, |paddle| paddle.translation.x);
+    for _ in collected_events.iter() {
+        queue(SoundKind::PowerUp, paddle_x);
+    }
+    for _ in life_lost_events.iter() {
+        queue(SoundKind::BallLost, 0.0);
+    }
+    for _ in level_cleared_events.iter() {
+        queue(SoundKind::LevelClear, 0.0);
+    }
+
+    let Some(sounds) = sounds else {
+        return;
+    };
+    let voices_left = config
+        .sounds
+        .max_voices
+        .saturating_sub(voice_query.iter().count());
+    let mut rng = rand::thread_rng();
+    for (kind, x) in to_play.into_iter().take(voices_left) {
+        let Some(set) = config.sounds.set(kind) else {
+            continue;
+        };
+        // A file that failed to load would hold on to its voice
+        // forever without playing
+        let files: Vec<_> = sounds
+            .0
+            .get(&kind)
+            .into_iter()
+            .flatten()
+            .filter(|file| {
+                asset_server.get_load_state(*file)
+                    != LoadState::Failed
+            })
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        let source =
+            files[rng.gen_range(0..files.len())].clone();
+        let volume = set.volume
+            * (1.0
+                + rng.gen_range(
+                    -set.volume_variation..=set.volume_variation,
+                ));
+        let pitch = set.pitch
+            * (1.0
+                + rng.gen_range(
+                    -set.pitch_variation..=set.pitch_variation,
+                ));
+
+        // The listener's ears are one unit either side of the
+        // middle of the arena, so that the sound only pans rather
+        // than fading with distance
+        let pan = (x / (config.arena_size().x / 2.0))
+            .clamp(-1.0, 1.0)
+            * config.sounds.stereo_width;
+        commands.spawn((
+            SpatialAudioBundle {
+                source,
+                settings: PlaybackSettings::DESPAWN
+                    .with_volume(Volume::new_relative(volume))
+                    .with_speed(pitch),
+                spatial: SpatialSettings::new(
+                    Transform::IDENTITY,
+                    2.0,
+                    Vec3::new(pan, 0.0, 0.0),
+                ),
+            },
+            SoundEffect,
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+Usage: breakout [OPTIONS]
+
+Options:
+  --headless         Run the game without a window, with a bot
+                     playing, and print the final score
+  --max-steps <N>    Stop a headless run after N fixed steps
+  --level <N>        Start new games on the Nth level
+  --seed <SEED>      Seed the randomness of every game
+  --record <FILE>    Save a replay of each game to FILE
+  --replay <FILE>    Play the replay in FILE back and check that it
+                     ends the same way, which needs the settings it
+                     was recorded with
+  --config <FILE>    Read the game settings from the RON file FILE
+  --set <KEY=VALUE>  Override a single game setting, such as
+                     `ball_speed=650`; can be repeated";
+
+#[derive(Debug, Error)]
+pub enum ArgsError {
+    #[error("unknown argument `{0}`")]
+    UnknownArgument(String),
+    #[error("`{0}` expects a value")]
+    MissingValue(&'static str),
+    #[error("invalid value `{value}` for `{flag}`")]
+    InvalidValue { flag: &'static str, value: String },
+}
+
+/// Options given on the command line.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub headless: bool,
+    pub max_steps: Option<u64>,
+    /// Index of the level to start on.
+    pub level: usize,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    /// Settings to override, as (setting, value) pairs.
+    pub settings: Vec<(String, String)>,
+}
+
+impl Args {
+    /// Parses the process' arguments, exiting with the usage on error.
+    pub fn from_env() -> Args {
+        match Args::parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(error) => {
+                eprintln!("error: {error}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Args, ArgsError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--max-steps" => {
+                    parsed.max_steps = Some(parse_value(
+                        "--max-steps",
+                        args.next(),
+                    )?);
+                }
+                "--level" => {
+                    let value = args.next();
+                    let level: usize =
+                        parse_value("--level", value.clone())?;
+                    // Levels are numbered from 1 for players
+                    parsed.level = level.checked_sub(1).ok_or(
+                        ArgsError::InvalidValue {
+                            flag: "--level",
+                            value: value.unwrap_or_default(),
+                        },
+                    )?;
+                }
+                "--seed" => {
+                    parsed.seed = Some(parse_value(
+                        "--seed",
+                        args.next(),
+                    )?);
+                }
+                "--record" => {
+                    parsed.record = Some(parse_value(
+                        "--record",
+                        args.next(),
+                    )?);
+                }
+                "--replay" => {
+                    parsed.replay = Some(parse_value(
+                        "--replay",
+                        args.next(),
+                    )?);
+                }
+                "--config" => {
+                    parsed.config = Some(parse_value(
+                        "--config",
+                        args.next(),
+                    )?);
+                }
+                "--set" => {
+                    let value: String =
+                        parse_value("--set", args.next())?;
+                    let (setting, setting_value) =
+                        value.split_once('=').ok_or_else(
+                            || ArgsError::InvalidValue {
+                                flag: "--set",
+                                value: value.clone(),
+                            },
+                        )?;
+                    parsed.settings.push((
+                        setting.trim().to_string(),
+                        setting_value.trim().to_string(),
+                    ));
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => {
+                    return Err(ArgsError::UnknownArgument(arg))
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(
+    flag: &'static str,
+    value: Option<String>,
+) -> Result<T, ArgsError> {
+    let value = value.ok_or(ArgsError::MissingValue(flag))?;
+    value
+        .parse()
+        .map_err(|_| ArgsError::InvalidValue { flag, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn settings_are_split_at_the_first_equals_sign() {
+        let args = parse(&[
+            "--set",
+            "ball_speed = 650",
+            "--set",
+            "background_color=#a=b",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.settings,
+            [
+                ("ball_speed".to_owned(), "650".to_owned()),
+                (
+                    "background_color".to_owned(),
+                    "#a=b".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn setting_without_a_value_is_rejected() {
+        assert!(matches!(
+            parse(&["--set", "ball_speed"]),
+            Err(ArgsError::InvalidValue { flag: "--set", .. })
+        ));
+        assert!(matches!(
+            parse(&["--set"]),
+            Err(ArgsError::MissingValue("--set"))
+        ));
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert!(matches!(
+            parse(&["--level", "0"]),
+            Err(ArgsError::InvalidValue {
+                flag: "--level",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["--seed", "lucky"]),
+            Err(ArgsError::InvalidValue { flag: "--seed", .. })
+        ));
+        assert!(matches!(
+            parse(&["--fast"]),
+            Err(ArgsError::UnknownArgument(arg)) if arg == "--fast"
+        ));
+    }
+}
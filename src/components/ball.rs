@@ -1,12 +1,240 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+
+use super::paddle::Paddle;
+use crate::{
+    config::GameConfig, input::PaddleIntent, physics::Velocity,
+};
 
 pub const BALL_RADIUS: f32 = 15.0;
-pub const BALL_STARTING_POSITION: Vec3 =
-    Vec3::new(0.0, -50.0, 1.0);
-pub const BALL_SIZE: Vec3 =
-    Vec3::new(BALL_RADIUS * 2.0, BALL_RADIUS * 2.0, 0.0);
-pub const INITIAL_BALL_DIRECTION: Vec2 = Vec2::new(0.5, -0.5);
+// Draws balls in front of the paddle and bricks
+pub const BALL_Z: f32 = 1.0;
 pub const BALL_SPEED: f32 = 500.0;
+// Fraction of `BALL_SPEED` added for every level after the first
+pub const BALL_SPEED_INCREASE_PER_LEVEL: f32 = 0.1;
+// Seconds a served ball waits on the paddle before launching itself
+pub const AUTO_LAUNCH_DELAY: f32 = 5.0;
 
+/// A ball in play. It is moved by the physics rather than by its
+/// [`Velocity`] alone.
 #[derive(Component)]
 pub struct Ball;
+
+/// Holds a ball on top of the paddle, `offset` away from its center,
+/// until it is launched.
+#[derive(Component)]
+pub struct StuckToPaddle {
+    pub offset: Vec2,
+}
+
+/// Marks a ball served on the paddle at the start of a life or
+/// level. It has no velocity until it is launched in the direction
+/// given by [`Paddle::serve_direction`].
+#[derive(Component)]
+pub struct Serving {
+    /// Launches the ball once it runs out, unless auto-launch is
+    /// turned off.
+    pub timer: Option<Timer>,
+}
+
+/// Speed at which balls are served in the current level.
+#[derive(Resource)]
+pub struct BallSpeed(pub f32);
+
+/// Mesh and material shared by every ball, so that respawning a ball
+/// doesn't allocate new assets.
+#[derive(Resource)]
+pub struct BallAssets {
+    /// Scale of the unit circle mesh, from the configured radius.
+    pub size: Vec3,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ColorMaterial>,
+    pub fireball_material: Handle<ColorMaterial>,
+}
+
+impl BallAssets {
+    pub fn new(
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<ColorMaterial>,
+        config: &GameConfig,
+    ) -> BallAssets {
+        let diameter = config.ball_radius * 2.0;
+        BallAssets {
+            size: Vec3::new(diameter, diameter, 0.0),
+            mesh: meshes.add(shape::Circle::default().into()),
+            material: materials
+                .add(ColorMaterial::from(config.ball_color)),
+            fireball_material: materials
+                .add(ColorMaterial::from(config.fireball_color)),
+        }
+    }
+}
+
+/// Resizes every ball and recolors the shared materials to match the
+/// config.
+pub fn update_ball_assets(
+    config: Res<GameConfig>,
+    mut ball_assets: ResMut<BallAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut ball_query: Query<&mut Transform, With<Ball>>,
+) {
+    let diameter = config.ball_radius * 2.0;
+    ball_assets.size = Vec3::new(diameter, diameter, 0.0);
+    for mut transform in &mut ball_query {
+        transform.scale = ball_assets.size;
+    }
+
+    if let Some(material) =
+        materials.get_mut(&ball_assets.material)
+    {
+        material.color = config.ball_color;
+    }
+    if let Some(material) =
+        materials.get_mut(&ball_assets.fireball_material)
+    {
+        material.color = config.fireball_color;
+    }
+}
+
+pub fn setup_ball_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
+) {
+    commands.insert_resource(BallAssets::new(
+        &mut meshes,
+        &mut materials,
+        &config,
+    ));
+}
+
+#[derive(Bundle)]
+pub struct BallBundle {
+    pub mesh_bundle: MaterialMesh2dBundle<ColorMaterial>,
+    pub velocity: Velocity,
+    ball: Ball,
+}
+
+impl BallBundle {
+    pub fn with_motion(
+        assets: &BallAssets,
+        translation: Vec3,
+        velocity: Vec2,
+    ) -> BallBundle {
+        BallBundle {
+            mesh_bundle: MaterialMesh2dBundle {
+                mesh: assets.mesh.clone().into(),
+                material: assets.material.clone(),
+                transform: Transform::from_translation(
+                    translation,
+                )
+                .with_scale(assets.size),
+                ..default()
+            },
+            velocity: Velocity(velocity),
+            ball: Ball,
+        }
+    }
+}
+
+/// A ball resting on top of the paddle, waiting to be launched.
+#[derive(Bundle)]
+pub struct ServedBallBundle {
+    pub ball: BallBundle,
+    pub stuck: StuckToPaddle,
+    pub serving: Serving,
+}
+
+impl ServedBallBundle {
+    pub fn new(
+        assets: &BallAssets,
+        paddle_x: f32,
+        config: &GameConfig,
+    ) -> ServedBallBundle {
+        let offset = Vec2::new(
+            0.0,
+            config.paddle_size.y / 2.0 + config.ball_radius,
+        );
+        let timer =
+            (config.auto_launch_delay > 0.0).then(|| {
+                Timer::from_seconds(
+                    config.auto_launch_delay,
+                    TimerMode::Once,
+                )
+            });
+
+        ServedBallBundle {
+            ball: BallBundle::with_motion(
+                assets,
+                Vec3::new(
+                    paddle_x,
+                    config.paddle_y() + offset.y,
+                    BALL_Z,
+                ),
+                Vec2::ZERO,
+            ),
+            stuck: StuckToPaddle { offset },
+            serving: Serving { timer },
+        }
+    }
+}
+
+pub fn follow_paddle(
+    mut ball_query: Query<
+        (&mut Transform, &StuckToPaddle),
+        Without<Paddle>,
+    >,
+    paddle_query: Query<&Transform, With<Paddle>>,
+) {
+    let paddle_transform = paddle_query.single();
+    for (mut ball_transform, stuck) in &mut ball_query {
+        let position = paddle_transform.translation.truncate()
+            + stuck.offset;
+        ball_transform.translation.x = position.x;
+        ball_transform.translation.y = position.y;
+    }
+}
+
+/// Sends every ball held by the paddle off when the player asks for
+/// it. Caught balls leave the way they would have bounced, and served
+/// balls leave in the paddle's serve direction, by themselves if the
+/// player waits too long.
+pub fn launch_stuck_balls(
+    mut commands: Commands,
+    mut intent: ResMut<PaddleIntent>,
+    time_step: Res<FixedTime>,
+    ball_speed: Res<BallSpeed>,
+    config: Res<GameConfig>,
+    paddle_query: Query<(&Transform, &Paddle)>,
+    mut ball_query: Query<
+        (Entity, &mut Velocity, Option<&mut Serving>),
+        With<StuckToPaddle>,
+    >,
+) {
+    let launch = std::mem::take(&mut intent.launch);
+    let (paddle_transform, paddle) = paddle_query.single();
+
+    for (entity, mut velocity, serving) in &mut ball_query {
+        let Some(mut serving) = serving else {
+            if launch {
+                commands
+                    .entity(entity)
+                    .remove::<StuckToPaddle>();
+            }
+            continue;
+        };
+
+        let timed_out =
+            serving.timer.as_mut().is_some_and(|timer| {
+                timer.tick(time_step.period).finished()
+            });
+        if launch || timed_out {
+            velocity.0 = paddle
+                .serve_direction(paddle_transform, &config)
+                * ball_speed.0;
+            commands
+                .entity(entity)
+                .remove::<(StuckToPaddle, Serving)>();
+        }
+    }
+}
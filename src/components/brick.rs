@@ -5,6 +5,193 @@ pub const GAP_BETWEEN_PADDLE_AND_BRICKS: f32 = 400.0;
 pub const GAP_BETWEEN_BRICKS: f32 = 5.0;
 pub const GAP_BETWEEN_BRICKS_AND_CEILING: f32 = 50.0;
 pub const GAP_BETWEEN_BRICKS_AND_SIDES: f32 = 50.0;
+// How much darker a brick gets just before it breaks
+pub const BRICK_DAMAGE_DARKENING: f32 = 0.6;
 
-#[derive(Component)]
-pub struct Brick;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    Breakable {
+        hits_left: u32,
+        max_hits: u32,
+    },
+    /// Steel bricks only reflect the ball and never break.
+    Indestructible,
+}
+
+/// What a single hit did to a brick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrickHit {
+    Damaged,
+    Destroyed,
+    Deflected,
+}
+
+/// Everything a level needs to know to spawn a brick.
+#[derive(Debug, Clone, Copy)]
+pub struct BrickType {
+    /// Stands for the type in level grids, and picks its sound set.
+    pub symbol: char,
+    pub color: Color,
+    pub durability: Durability,
+    /// Added to the score when the brick is destroyed.
+    pub points: usize,
+}
+
+impl BrickType {
+    pub fn breakable(
+        symbol: char,
+        color: Color,
+        hits: u32,
+        points: usize,
+    ) -> Self {
+        BrickType {
+            symbol,
+            color,
+            durability: Durability::Breakable {
+                hits_left: hits,
+                max_hits: hits,
+            },
+            points,
+        }
+    }
+
+    pub fn indestructible(symbol: char, color: Color) -> Self {
+        BrickType {
+            symbol,
+            color,
+            durability: Durability::Indestructible,
+            points: 0,
+        }
+    }
+
+    pub fn is_breakable(&self) -> bool {
+        matches!(self.durability, Durability::Breakable { .. })
+    }
+}
+
+/// A brick of the level, which the ball has to clear.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Brick(pub BrickType);
+
+impl Brick {
+    pub fn is_breakable(&self) -> bool {
+        self.0.is_breakable()
+    }
+
+    pub fn points(&self) -> usize {
+        self.0.points
+    }
+
+    pub fn hit(&mut self) -> BrickHit {
+        match &mut self.0.durability {
+            Durability::Breakable { hits_left, .. } => {
+                *hits_left = hits_left.saturating_sub(1);
+                if *hits_left == 0 {
+                    BrickHit::Destroyed
+                } else {
+                    BrickHit::Damaged
+                }
+            }
+            Durability::Indestructible => BrickHit::Deflected,
+        }
+    }
+
+    /// The brick's color, darkened according to the damage taken.
+    pub fn color(&self) -> Color {
+        let Durability::Breakable {
+            hits_left,
+            max_hits,
+        } = self.0.durability
+        else {
+            return self.0.color;
+        };
+
+        // A brick on its last hit is darkened the full amount
+        let damage = if max_hits > 1 {
+            (max_hits - hits_left) as f32 / (max_hits - 1) as f32
+        } else {
+            0.0
+        };
+        let brightness = 1.0 - BRICK_DAMAGE_DARKENING * damage;
+        let [r, g, b, a] = self.0.color.as_rgba_f32();
+        Color::rgba(
+            r * brightness,
+            g * brightness,
+            b * brightness,
+            a,
+        )
+    }
+}
+
+pub fn update_brick_color(
+    mut query: Query<(&Brick, &mut Sprite), Changed<Brick>>,
+) {
+    for (brick, mut sprite) in &mut query {
+        sprite.color = brick.color();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(1.0, 0.0, 0.0);
+
+    #[test]
+    fn breakable_brick_takes_its_hits_then_breaks() {
+        let mut brick =
+            Brick(BrickType::breakable('3', RED, 3, 5));
+        assert!(brick.is_breakable());
+        assert_eq!(brick.points(), 5);
+        assert_eq!(brick.hit(), BrickHit::Damaged);
+        assert_eq!(brick.hit(), BrickHit::Damaged);
+        assert_eq!(brick.hit(), BrickHit::Destroyed);
+    }
+
+    #[test]
+    fn single_hit_brick_breaks_at_once() {
+        let mut brick =
+            Brick(BrickType::breakable('R', RED, 1, 1));
+        assert_eq!(brick.hit(), BrickHit::Destroyed);
+    }
+
+    #[test]
+    fn indestructible_brick_only_deflects() {
+        let mut brick =
+            Brick(BrickType::indestructible('#', RED));
+        assert!(!brick.is_breakable());
+        assert_eq!(brick.points(), 0);
+        for _ in 0..10 {
+            assert_eq!(brick.hit(), BrickHit::Deflected);
+        }
+        assert_eq!(brick.color(), RED);
+    }
+
+    #[test]
+    fn brick_darkens_with_damage() {
+        let mut brick =
+            Brick(BrickType::breakable('3', RED, 3, 5));
+        assert_eq!(brick.color(), RED);
+
+        brick.hit();
+        let [r, g, b, a] = brick.color().as_rgba_f32();
+        assert!(
+            (r - (1.0 - BRICK_DAMAGE_DARKENING / 2.0)).abs()
+                < 1e-6
+        );
+        assert_eq!([g, b, a], [0.0, 0.0, 1.0]);
+
+        // Fully darkened on its last hit
+        brick.hit();
+        let [r, ..] = brick.color().as_rgba_f32();
+        assert!(
+            (r - (1.0 - BRICK_DAMAGE_DARKENING)).abs() < 1e-6
+        );
+    }
+
+    #[test]
+    fn single_hit_brick_keeps_its_color() {
+        let brick = Brick(BrickType::breakable('R', RED, 1, 1));
+        assert_eq!(brick.color(), RED);
+    }
+}
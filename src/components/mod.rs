@@ -5,4 +5,4 @@ pub mod wall;
 
 pub const SCREEN_HEIGHT: f32 = 800.0;
 pub const SCREEN_WIDTH: f32 = 1200.0;
-pub const WALL_THICKNESS: f32 = 4.0;
\ No newline at end of file
+pub const WALL_THICKNESS: f32 = 4.0;
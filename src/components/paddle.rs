@@ -1,46 +1,285 @@
 use bevy::prelude::*;
 
-use super::{wall::{LEFT_WALL, RIGHT_WALL}, WALL_THICKNESS};
+use crate::{config::GameConfig, input::PaddleIntent};
 
 pub const PADDLE_SPEED: f32 = 900.0;
 pub const PADDLE_SIZE: Vec3 = Vec3::new(120.0, 20.0, 0.0);
 pub const PADDLE_PADDING: f32 = 0.0;
 pub const GAP_BETWEEN_PADDLE_AND_FLOOR: f32 = 60.0;
+// Angle from vertical at which the ball leaves the paddle's edges
+pub const MAX_BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+// Extra angle given to the ball by a paddle moving at full speed
+pub const PADDLE_SPIN_ANGLE: f32 = std::f32::consts::PI / 12.0;
+// Angle from vertical at which a ball is served from a paddle
+// against a wall, aiming it back towards the middle
+pub const MAX_SERVE_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
 
+/// The player's paddle, the only thing they control.
+#[derive(Component, Default)]
+pub struct Paddle {
+    /// Horizontal velocity over the last fixed step.
+    pub velocity: f32,
+}
+
+impl Paddle {
+    /// Direction of a ball bouncing off the top of the paddle at `ball_x`.
+    ///
+    /// Hits near the center go straight up and hits near the edges go
+    /// out at up to [`MAX_BOUNCE_ANGLE`], nudged further in the
+    /// direction the paddle is moving.
+    pub fn rebound_direction(
+        &self,
+        paddle_transform: &Transform,
+        ball_x: f32,
+        config: &GameConfig,
+    ) -> Vec2 {
+        let half_width = paddle_transform.scale.x / 2.0;
+        let offset = ((ball_x - paddle_transform.translation.x)
+            / half_width)
+            .clamp(-1.0, 1.0);
+        let spin = self.velocity / config.paddle_speed
+            * PADDLE_SPIN_ANGLE;
+        let angle = (offset * MAX_BOUNCE_ANGLE + spin)
+            .clamp(-MAX_BOUNCE_ANGLE, MAX_BOUNCE_ANGLE);
+
+        Vec2::new(angle.sin(), angle.cos())
+    }
 
-#[derive(Component)]
-pub struct Paddle;
+    /// Direction of a ball served from the paddle.
+    ///
+    /// A paddle in the middle of the arena serves straight up, and
+    /// one further out serves towards the middle at up to
+    /// [`MAX_SERVE_ANGLE`], nudged in the direction the paddle is
+    /// moving.
+    pub fn serve_direction(
+        &self,
+        paddle_transform: &Transform,
+        config: &GameConfig,
+    ) -> Vec2 {
+        let position = (paddle_transform.translation.x
+            / (config.arena_size().x / 2.0))
+            .clamp(-1.0, 1.0);
+        let spin = self.velocity / config.paddle_speed
+            * PADDLE_SPIN_ANGLE;
+        let angle = (-position * MAX_SERVE_ANGLE + spin)
+            .clamp(-MAX_BOUNCE_ANGLE, MAX_BOUNCE_ANGLE);
+
+        Vec2::new(angle.sin(), angle.cos())
+    }
+}
 
 pub fn move_paddle(
-    mut query: Query<&mut Transform, With<Paddle>>,
-    input: Res<Input<KeyCode>>,
+    mut query: Query<(&mut Transform, &mut Paddle)>,
+    intent: Res<PaddleIntent>,
     time_step: Res<FixedTime>,
+    config: Res<GameConfig>,
 ) {
-    let mut paddle_transform = query.single_mut();
+    let (mut paddle_transform, mut paddle) = query.single_mut();
+    let dt = time_step.period.as_secs_f32();
+    let old_paddle_position = paddle_transform.translation.x;
+
+    let new_paddle_position = match intent.target {
+        // Close most of the distance to a pointed-at position within
+        // `mouse_smoothing` seconds
+        Some(target) => {
+            let follow = if config.mouse_smoothing > 0.0 {
+                1.0 - (-dt / config.mouse_smoothing).exp()
+            } else {
+                1.0
+            };
+            old_paddle_position
+                + (target - old_paddle_position) * follow
+        }
+        None => {
+            old_paddle_position
+                + intent.direction.clamp(-1.0, 1.0)
+                    * config.paddle_speed
+                    * dt
+        }
+    };
 
-    let mut direction = 0.0;
+    // The paddle's width changes with power-ups, so bound it by its
+    // current scale rather than the configured size
+    let half_width = paddle_transform.scale.x / 2.0;
+    let left_bound = config.left_wall()
+        + config.wall_thickness
+        + half_width
+        + config.paddle_padding;
+    let right_bound = config.right_wall()
+        - config.wall_thickness
+        - half_width
+        - config.paddle_padding;
 
-    if input.pressed(KeyCode::Left) {
-        direction -= 1.0;
+    paddle_transform.translation.x =
+        new_paddle_position.clamp(left_bound, right_bound);
+    paddle.velocity = (paddle_transform.translation.x
+        - old_paddle_position)
+        / dt;
+}
+
+/// Moves, resizes and recolors the paddle to match the config. Its
+/// width is kept up to date with the power-ups by the fixed step.
+pub fn update_paddle(
+    config: Res<GameConfig>,
+    mut paddle_query: Query<
+        (&mut Transform, &mut Sprite),
+        With<Paddle>,
+    >,
+) {
+    for (mut transform, mut sprite) in &mut paddle_query {
+        transform.translation.y = config.paddle_y();
+        transform.scale.y = config.paddle_size.y;
+        sprite.color = config.paddle_color;
     }
-    if input.pressed(KeyCode::Right) {
-        direction += 1.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Angle of `direction` from straight up, positive to the right.
+    fn angle(direction: Vec2) -> f32 {
+        direction.x.atan2(direction.y)
     }
 
-    let new_paddle_position = paddle_transform.translation.x
-        + direction
-            * PADDLE_SPEED
-            * time_step.period.as_secs_f32();
+    fn assert_angle(direction: Vec2, expected: f32) {
+        assert!(
+            (angle(direction) - expected).abs() < 1e-5,
+            "{direction} is not at {expected} radians"
+        );
+    }
 
-    let left_bound = LEFT_WALL
-        + WALL_THICKNESS
-        + PADDLE_SIZE.x / 2.0
-        + PADDLE_PADDING;
-    let right_bound = RIGHT_WALL
-        - WALL_THICKNESS
-        - PADDLE_SIZE.x / 2.0
-        - PADDLE_PADDING;
+    fn paddle_at(x: f32) -> Transform {
+        Transform::from_xyz(x, 0.0, 0.0)
+            .with_scale(Vec3::new(120.0, 20.0, 0.0))
+    }
 
-    paddle_transform.translation.x =
-        new_paddle_position.clamp(left_bound, right_bound);
+    fn moving(fraction_of_full_speed: f32) -> Paddle {
+        Paddle {
+            velocity: fraction_of_full_speed
+                * GameConfig::default().paddle_speed,
+        }
+    }
+
+    #[test]
+    fn hit_at_the_center_goes_straight_up() {
+        let config = GameConfig::default();
+        let direction = moving(0.0).rebound_direction(
+            &paddle_at(100.0),
+            100.0,
+            &config,
+        );
+        assert_eq!(direction, Vec2::Y);
+    }
+
+    #[test]
+    fn hits_at_the_edges_go_out_at_the_bounce_angle() {
+        let config = GameConfig::default();
+        let paddle = moving(0.0);
+        let transform = paddle_at(100.0);
+        assert_angle(
+            paddle.rebound_direction(&transform, 160.0, &config),
+            MAX_BOUNCE_ANGLE,
+        );
+        assert_angle(
+            paddle.rebound_direction(&transform, 40.0, &config),
+            -MAX_BOUNCE_ANGLE,
+        );
+        // Halfway out goes out at half the angle
+        assert_angle(
+            paddle.rebound_direction(&transform, 130.0, &config),
+            MAX_BOUNCE_ANGLE / 2.0,
+        );
+        // The ball's center can be past the edge as it clips it
+        assert_angle(
+            paddle.rebound_direction(&transform, 175.0, &config),
+            MAX_BOUNCE_ANGLE,
+        );
+    }
+
+    #[test]
+    fn moving_paddle_spins_the_ball_up_to_the_bounce_angle() {
+        let config = GameConfig::default();
+        let transform = paddle_at(0.0);
+        assert_angle(
+            moving(1.0)
+                .rebound_direction(&transform, 0.0, &config),
+            PADDLE_SPIN_ANGLE,
+        );
+        assert_angle(
+            moving(-0.5)
+                .rebound_direction(&transform, 0.0, &config),
+            -PADDLE_SPIN_ANGLE / 2.0,
+        );
+        assert_angle(
+            moving(1.0)
+                .rebound_direction(&transform, 60.0, &config),
+            MAX_BOUNCE_ANGLE,
+        );
+        assert_angle(
+            moving(-1.0)
+                .rebound_direction(&transform, -60.0, &config),
+            -MAX_BOUNCE_ANGLE,
+        );
+    }
+
+    #[test]
+    fn serve_aims_back_towards_the_middle() {
+        let config = GameConfig::default();
+        let paddle = moving(0.0);
+        let edge = config.arena_size().x / 2.0;
+        assert_eq!(
+            paddle.serve_direction(&paddle_at(0.0), &config),
+            Vec2::Y
+        );
+        assert_angle(
+            paddle.serve_direction(&paddle_at(edge), &config),
+            -MAX_SERVE_ANGLE,
+        );
+        assert_angle(
+            paddle.serve_direction(&paddle_at(-edge), &config),
+            MAX_SERVE_ANGLE,
+        );
+        assert_angle(
+            moving(1.0)
+                .serve_direction(&paddle_at(-edge), &config),
+            MAX_SERVE_ANGLE + PADDLE_SPIN_ANGLE,
+        );
+        // However fast the paddle moves, the serve is no flatter
+        // than a bounce
+        assert_angle(
+            moving(3.0)
+                .serve_direction(&paddle_at(-edge), &config),
+            MAX_BOUNCE_ANGLE,
+        );
+    }
+
+    #[test]
+    fn directions_are_normalized_and_point_up() {
+        let config = GameConfig::default();
+        let transform = paddle_at(0.0);
+        for speed in [-3.0, -1.0, 0.0, 0.5, 3.0] {
+            let paddle = moving(speed);
+            for step in -10..=10 {
+                let x = step as f32 * 10.0;
+                for direction in [
+                    paddle.rebound_direction(
+                        &transform, x, &config,
+                    ),
+                    paddle.serve_direction(
+                        &paddle_at(x * 50.0),
+                        &config,
+                    ),
+                ] {
+                    assert!(
+                        (direction.length() - 1.0).abs() < 1e-6
+                    );
+                    assert!(
+                        angle(direction).abs()
+                            <= MAX_BOUNCE_ANGLE + 1e-6
+                    );
+                }
+            }
+        }
+    }
 }
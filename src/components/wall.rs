@@ -1,14 +1,9 @@
 use bevy::prelude::*;
 
-use crate::{physics::Collider, ui::WALL_COLOR};
-
-use super::{SCREEN_HEIGHT, SCREEN_WIDTH, WALL_THICKNESS};
-
-pub const LEFT_WALL: f32 = -(SCREEN_WIDTH) / 2.0;
-pub const RIGHT_WALL: f32 = (SCREEN_WIDTH) / 2.0;
-pub const TOP_WALL: f32 = (SCREEN_HEIGHT) / 2.0;
-pub const BOTTOM_WALL: f32 = -(SCREEN_HEIGHT) / 2.0;
+use crate::{config::GameConfig, physics::Collider};
 
+/// The sides of the arena.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WallLocation {
     Left,
     Right,
@@ -17,61 +12,120 @@ pub enum WallLocation {
 }
 
 impl WallLocation {
-    pub fn position(&self) -> Vec2 {
+    pub fn position(&self, config: &GameConfig) -> Vec2 {
         match self {
-            WallLocation::Left => Vec2::new(LEFT_WALL, 0.),
-            WallLocation::Right => Vec2::new(RIGHT_WALL, 0.),
-            WallLocation::Bottom => Vec2::new(0., BOTTOM_WALL),
-            WallLocation::Top => Vec2::new(0., TOP_WALL),
+            WallLocation::Left => {
+                Vec2::new(config.left_wall(), 0.)
+            }
+            WallLocation::Right => {
+                Vec2::new(config.right_wall(), 0.)
+            }
+            WallLocation::Bottom => {
+                Vec2::new(0., config.bottom_wall())
+            }
+            WallLocation::Top => {
+                Vec2::new(0., config.top_wall())
+            }
         }
     }
 
-    pub fn size(&self) -> Vec2 {
-        let arena_height = TOP_WALL - BOTTOM_WALL;
-        let arena_width = RIGHT_WALL - LEFT_WALL;
-
-        assert!(arena_width > 0.0);
-        assert!(arena_height > 0.0);
+    /// Size of the wall, for a config that has passed
+    /// [`GameConfig::validate`].
+    pub fn size(&self, config: &GameConfig) -> Vec2 {
+        let arena_height =
+            config.top_wall() - config.bottom_wall();
+        let arena_width =
+            config.right_wall() - config.left_wall();
+        let thickness = config.wall_thickness;
 
         match self {
             WallLocation::Left | WallLocation::Right => {
-                Vec2::new(
-                    WALL_THICKNESS,
-                    arena_height + WALL_THICKNESS,
-                )
+                Vec2::new(thickness, arena_height + thickness)
             }
             WallLocation::Bottom | WallLocation::Top => {
-                Vec2::new(
-                    arena_width + WALL_THICKNESS,
-                    WALL_THICKNESS,
-                )
+                Vec2::new(arena_width + thickness, thickness)
             }
         }
     }
 }
 
+/// The floor of the arena. Balls that reach it are lost instead of
+/// being reflected.
+#[derive(Component)]
+pub struct KillZone;
+
 #[derive(Bundle)]
 pub struct WallBundle {
     pub sprite_bundle: SpriteBundle,
+    location: WallLocation,
     collider: Collider,
 }
 
 impl WallBundle {
-    pub fn new(location: WallLocation) -> WallBundle {
+    pub fn new(
+        location: WallLocation,
+        config: &GameConfig,
+    ) -> WallBundle {
         WallBundle {
-            sprite_bundle: SpriteBundle {
-                transform: Transform {
-                    translation: location.position().extend(0.0),
-                    scale: location.size().extend(1.0),
-                    ..Default::default()
-                },
-                sprite: Sprite {
-                    color: WALL_COLOR,
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
+            sprite_bundle: wall_sprite(&location, config),
+            location,
             collider: Collider,
         }
     }
 }
+
+#[derive(Bundle)]
+pub struct KillZoneBundle {
+    pub sprite_bundle: SpriteBundle,
+    location: WallLocation,
+    kill_zone: KillZone,
+}
+
+impl KillZoneBundle {
+    pub fn new(config: &GameConfig) -> KillZoneBundle {
+        KillZoneBundle {
+            sprite_bundle: wall_sprite(
+                &WallLocation::Bottom,
+                config,
+            ),
+            location: WallLocation::Bottom,
+            kill_zone: KillZone,
+        }
+    }
+}
+
+fn wall_sprite(
+    location: &WallLocation,
+    config: &GameConfig,
+) -> SpriteBundle {
+    SpriteBundle {
+        transform: Transform {
+            translation: location.position(config).extend(0.0),
+            scale: location.size(config).extend(1.0),
+            ..Default::default()
+        },
+        sprite: Sprite {
+            color: config.wall_color,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Moves, resizes and recolors the walls to match the config.
+pub fn update_walls(
+    config: Res<GameConfig>,
+    mut wall_query: Query<(
+        &WallLocation,
+        &mut Transform,
+        &mut Sprite,
+    )>,
+) {
+    for (location, mut transform, mut sprite) in &mut wall_query
+    {
+        transform.translation =
+            location.position(&config).extend(0.0);
+        transform.scale = location.size(&config).extend(1.0);
+        sprite.color = config.wall_color;
+    }
+}
@@ -0,0 +1,939 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+    window::PrimaryWindow,
+};
+use ron::{value::Map, Value};
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::{
+    action::KeyBindings,
+    audio::SoundConfig,
+    components::{
+        ball::{
+            AUTO_LAUNCH_DELAY, BALL_RADIUS, BALL_SPEED,
+            BALL_SPEED_INCREASE_PER_LEVEL,
+        },
+        brick::{
+            BRICK_SIZE, GAP_BETWEEN_BRICKS,
+            GAP_BETWEEN_BRICKS_AND_CEILING,
+            GAP_BETWEEN_BRICKS_AND_SIDES,
+            GAP_BETWEEN_PADDLE_AND_BRICKS,
+        },
+        paddle::{
+            GAP_BETWEEN_PADDLE_AND_FLOOR, PADDLE_PADDING,
+            PADDLE_SIZE, PADDLE_SPEED,
+        },
+        SCREEN_HEIGHT, SCREEN_WIDTH, WALL_THICKNESS,
+    },
+    highscore::{data_dir, DATA_FOLDER},
+    input::{GRAB_CURSOR, MOUSE_SMOOTHING},
+    ui::{
+        BACKGROUND_COLOR, BALL_COLOR, FIREBALL_COLOR,
+        PADDLE_COLOR, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+    },
+};
+
+/// Config file read from the assets folder unless another one is
+/// given.
+pub const DEFAULT_CONFIG_PATH: &str = "breakout.config.ron";
+/// File in the user's data folder that settings changed in the game
+/// are saved to, leaving the config file as it was written.
+pub const USER_SETTINGS_FILE: &str = "settings.ron";
+
+/// Provides the [`GameConfig`], reading it from a config file and
+/// again every time the file changes.
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameConfig>()
+            .init_resource::<ConfigSource>()
+            .add_asset::<ConfigFile>()
+            .init_asset_loader::<ConfigLoader>()
+            .add_systems(Startup, load_config_file)
+            .add_systems(
+                Update,
+                (
+                    apply_config_file,
+                    update_window
+                        .run_if(resource_changed::<GameConfig>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read the config file: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse the config file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("invalid setting: {0}")]
+    Invalid(#[from] ron::Error),
+    #[error(
+        "the config file must hold a single `( ... )` struct"
+    )]
+    NotAStruct,
+    #[error("`{0}` must be greater than zero")]
+    NotPositive(&'static str),
+    #[error("`{0}` can't be negative")]
+    Negative(&'static str),
+    #[error(
+        "the walls are too thick to leave room for the arena"
+    )]
+    NoArena,
+    #[error(
+        "the paddle is {paddle} wide, but the arena is only {arena} \
+         wide"
+    )]
+    PaddleTooWide { paddle: f32, arena: f32 },
+    #[error(
+        "the gaps around the bricks leave no room for them between \
+         the paddle and the ceiling"
+    )]
+    NoRoomForBricks,
+    #[error("{first} and {second} are both bound to {key:?}")]
+    ConflictingBindings {
+        key: KeyCode,
+        first: &'static str,
+        second: &'static str,
+    },
+    #[error(
+        "`{setting}` of the `{set}` sounds is out of range"
+    )]
+    InvalidSound { set: String, setting: &'static str },
+}
+
+/// Every tuning value of the game. Anything missing from a config
+/// file keeps the value of the matching constant.
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub screen_width: f32,
+    pub screen_height: f32,
+    pub wall_thickness: f32,
+
+    pub paddle_speed: f32,
+    pub paddle_size: Vec2,
+    /// How close the paddle can get to the walls.
+    pub paddle_padding: f32,
+    pub gap_between_paddle_and_floor: f32,
+
+    pub ball_radius: f32,
+    pub ball_speed: f32,
+    /// Fraction of `ball_speed` added for every level after the first.
+    pub ball_speed_increase_per_level: f32,
+    /// Seconds a ball served on the paddle waits before launching
+    /// by itself. Zero leaves it there until the player launches it.
+    pub auto_launch_delay: f32,
+
+    pub brick_size: Vec2,
+    pub gap_between_bricks: f32,
+    pub gap_between_bricks_and_ceiling: f32,
+    pub gap_between_bricks_and_sides: f32,
+    pub gap_between_paddle_and_bricks: f32,
+
+    /// Seconds the paddle takes to cover most of the way to the
+    /// cursor. Zero makes it jump straight there.
+    pub mouse_smoothing: f32,
+    /// Confines and hides the cursor while playing.
+    pub grab_cursor: bool,
+
+    pub bindings: KeyBindings,
+
+    pub sounds: SoundConfig,
+
+    #[serde(deserialize_with = "deserialize_color")]
+    pub background_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub paddle_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub ball_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub fireball_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub wall_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub text_color: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub score_color: Color,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            screen_width: SCREEN_WIDTH,
+            screen_height: SCREEN_HEIGHT,
+            wall_thickness: WALL_THICKNESS,
+            paddle_speed: PADDLE_SPEED,
+            paddle_size: PADDLE_SIZE.truncate(),
+            paddle_padding: PADDLE_PADDING,
+            gap_between_paddle_and_floor:
+                GAP_BETWEEN_PADDLE_AND_FLOOR,
+            ball_radius: BALL_RADIUS,
+            ball_speed: BALL_SPEED,
+            ball_speed_increase_per_level:
+                BALL_SPEED_INCREASE_PER_LEVEL,
+            auto_launch_delay: AUTO_LAUNCH_DELAY,
+            brick_size: BRICK_SIZE,
+            gap_between_bricks: GAP_BETWEEN_BRICKS,
+            gap_between_bricks_and_ceiling:
+                GAP_BETWEEN_BRICKS_AND_CEILING,
+            gap_between_bricks_and_sides:
+                GAP_BETWEEN_BRICKS_AND_SIDES,
+            gap_between_paddle_and_bricks:
+                GAP_BETWEEN_PADDLE_AND_BRICKS,
+            mouse_smoothing: MOUSE_SMOOTHING,
+            grab_cursor: GRAB_CURSOR,
+            bindings: KeyBindings::default(),
+            sounds: SoundConfig::default(),
+            background_color: BACKGROUND_COLOR,
+            paddle_color: PADDLE_COLOR,
+            ball_color: BALL_COLOR,
+            fireball_color: FIREBALL_COLOR,
+            wall_color: WALL_COLOR,
+            text_color: TEXT_COLOR,
+            score_color: SCORE_COLOR,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Reads the config from a RON file, if any, then layers the user
+    /// settings and overrides of `source` on top of it, as
+    /// [`ConfigSource::build`] does.
+    pub fn load(
+        path: Option<&Path>,
+        source: &ConfigSource,
+    ) -> Result<(GameConfig, Option<String>), ConfigError> {
+        let settings = match path {
+            Some(path) => {
+                parse_settings(&fs::read_to_string(path)?)?
+            }
+            None => Map::new(),
+        };
+        source.build(settings)
+    }
+
+    /// Builds the config from the settings of a config file and the
+    /// overrides, as [`GameConfig::load`] does.
+    pub fn from_settings(
+        mut settings: Map,
+        overrides: &[(String, String)],
+    ) -> Result<GameConfig, ConfigError> {
+        for (setting, value) in overrides {
+            // Let strings such as `#ff8800` be given without quotes.
+            // RON reads a bare word as a unit struct.
+            let value = match ron::from_str(value) {
+                Ok(Value::Unit) | Err(_) => {
+                    Value::String(value.clone())
+                }
+                Ok(value) => value,
+            };
+            settings
+                .insert(Value::String(setting.clone()), value);
+        }
+
+        let config: GameConfig =
+            Value::Map(settings).into_rust()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that the values make up a playable arena.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (name, value) in [
+            ("screen_width", self.screen_width),
+            ("screen_height", self.screen_height),
+            ("wall_thickness", self.wall_thickness),
+            ("paddle_speed", self.paddle_speed),
+            ("paddle_size", self.paddle_size.min_element()),
+            ("ball_radius", self.ball_radius),
+            ("ball_speed", self.ball_speed),
+            ("brick_size", self.brick_size.min_element()),
+        ] {
+            if value.is_nan() || value <= 0.0 {
+                return Err(ConfigError::NotPositive(name));
+            }
+        }
+
+        for (name, value) in [
+            ("paddle_padding", self.paddle_padding),
+            (
+                "gap_between_paddle_and_floor",
+                self.gap_between_paddle_and_floor,
+            ),
+            (
+                "ball_speed_increase_per_level",
+                self.ball_speed_increase_per_level,
+            ),
+            ("auto_launch_delay", self.auto_launch_delay),
+            ("gap_between_bricks", self.gap_between_bricks),
+            (
+                "gap_between_bricks_and_ceiling",
+                self.gap_between_bricks_and_ceiling,
+            ),
+            (
+                "gap_between_bricks_and_sides",
+                self.gap_between_bricks_and_sides,
+            ),
+            (
+                "gap_between_paddle_and_bricks",
+                self.gap_between_paddle_and_bricks,
+            ),
+            ("mouse_smoothing", self.mouse_smoothing),
+            ("stereo_width", self.sounds.stereo_width),
+        ] {
+            if value.is_nan() || value < 0.0 {
+                return Err(ConfigError::Negative(name));
+            }
+        }
+
+        let arena = self.arena_size();
+        if arena.x <= 0.0 || arena.y <= 0.0 {
+            return Err(ConfigError::NoArena);
+        }
+
+        let paddle =
+            self.paddle_size.x + 2.0 * self.paddle_padding;
+        if paddle > arena.x {
+            return Err(ConfigError::PaddleTooWide {
+                paddle,
+                arena: arena.x,
+            });
+        }
+
+        let bricks_bottom =
+            self.paddle_y() + self.gap_between_paddle_and_bricks;
+        let bricks_top = self.top_wall()
+            - self.gap_between_bricks_and_ceiling;
+        if bricks_top - bricks_bottom < self.brick_size.y
+            || arena.x - 2.0 * self.gap_between_bricks_and_sides
+                < self.brick_size.x
+        {
+            return Err(ConfigError::NoRoomForBricks);
+        }
+
+        if let Some((first, second)) = self.bindings.conflict() {
+            return Err(ConfigError::ConflictingBindings {
+                key: self.bindings.key(first),
+                first: first.label(),
+                second: second.label(),
+            });
+        }
+
+        if let Some((kind, setting)) =
+            self.sounds.invalid_setting()
+        {
+            return Err(ConfigError::InvalidSound {
+                set: kind.setting(),
+                setting,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn left_wall(&self) -> f32 {
+        -self.screen_width / 2.0
+    }
+
+    pub fn right_wall(&self) -> f32 {
+        self.screen_width / 2.0
+    }
+
+    pub fn top_wall(&self) -> f32 {
+        self.screen_height / 2.0
+    }
+
+    pub fn bottom_wall(&self) -> f32 {
+        -self.screen_height / 2.0
+    }
+
+    /// Space between the inner faces of the walls.
+    pub fn arena_size(&self) -> Vec2 {
+        Vec2::new(self.screen_width, self.screen_height)
+            - self.wall_thickness
+    }
+
+    /// Height of the paddle's center.
+    pub fn paddle_y(&self) -> f32 {
+        self.bottom_wall() + self.gap_between_paddle_and_floor
+    }
+
+    /// Speed of the ball on the level at `index` of the campaign.
+    pub fn ball_speed(&self, index: usize) -> f32 {
+        self.ball_speed
+            * (1.0
+                + self.ball_speed_increase_per_level
+                    * index as f32)
+    }
+}
+
+/// Where the [`GameConfig`] comes from, kept to build it again when
+/// the file changes.
+#[derive(Resource, Debug, Clone)]
+pub struct ConfigSource {
+    /// Relative to the assets folder, unless absolute.
+    pub path: PathBuf,
+    /// Settings saved from the game, such as the key bindings, which
+    /// win over the file. `None` when there is nowhere to keep them.
+    pub user_settings: Option<PathBuf>,
+    /// Settings given on the command line, which win over both.
+    pub overrides: Vec<(String, String)>,
+}
+
+impl ConfigSource {
+    /// Where the user settings are kept on this machine, if anywhere.
+    pub fn default_user_settings() -> Option<PathBuf> {
+        data_dir().map(|dir| {
+            dir.join(DATA_FOLDER).join(USER_SETTINGS_FILE)
+        })
+    }
+
+    /// Builds the config from `settings`, those of the config file,
+    /// with the user settings and then the overrides layered on top.
+    ///
+    /// User settings that can't be read, or that leave the config
+    /// invalid, can't be changed from a game that won't start. They
+    /// are moved aside, as a corrupt high score table is, and the
+    /// config is built without them. A warning saying so is returned
+    /// along with it.
+    pub fn build(
+        &self,
+        settings: Map,
+    ) -> Result<(GameConfig, Option<String>), ConfigError> {
+        let Some(path) = &self.user_settings else {
+            let config = GameConfig::from_settings(
+                settings,
+                &self.overrides,
+            )?;
+            return Ok((config, None));
+        };
+        let layered =
+            read_settings(path).and_then(|user_settings| {
+                let mut settings = settings.clone();
+                for (setting, value) in user_settings.iter() {
+                    settings
+                        .insert(setting.clone(), value.clone());
+                }
+                GameConfig::from_settings(
+                    settings,
+                    &self.overrides,
+                )
+            });
+        let error = match layered {
+            Ok(config) => return Ok((config, None)),
+            Err(error) => error,
+        };
+
+        // Without them, any error is the config file's or the
+        // overrides' to report
+        let config = GameConfig::from_settings(
+            settings,
+            &self.overrides,
+        )?;
+        Ok((config, Some(set_aside_user_settings(path, &error))))
+    }
+}
+
+/// Moves user settings the game can't use out of the way, keeping
+/// them for whoever wants to recover them, and says what was done.
+fn set_aside_user_settings(
+    path: &Path,
+    error: &ConfigError,
+) -> String {
+    let backup = path.with_extension("ron.corrupt");
+    match fs::rename(path, &backup) {
+        Ok(()) => format!(
+            "Ignoring the user settings in {path:?}, as {error}. \
+             Moved them to {backup:?}"
+        ),
+        Err(rename_error) => format!(
+            "Ignoring the user settings in {path:?}, as {error}, \
+             and could not move them to {backup:?}: {rename_error}"
+        ),
+    }
+}
+
+impl Default for ConfigSource {
+    fn default() -> Self {
+        ConfigSource {
+            path: DEFAULT_CONFIG_PATH.into(),
+            user_settings: ConfigSource::default_user_settings(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// The settings written in a config file, before the overrides are
+/// applied and the result is checked.
+#[derive(Debug, TypeUuid, TypePath)]
+#[uuid = "3b7e5c2a-8f41-4d6b-9a0e-6c1d2f8b7e94"]
+pub struct ConfigFile(pub Map);
+
+#[derive(Default)]
+pub struct ConfigLoader;
+
+impl AssetLoader for ConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            let settings = parse_settings(text)?;
+            load_context.set_default_asset(LoadedAsset::new(
+                ConfigFile(settings),
+            ));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        // Config files named anything else are still RON
+        &["config.ron", "ron"]
+    }
+}
+
+/// The config file being watched.
+#[derive(Resource)]
+pub struct ConfigHandle {
+    pub handle: Handle<ConfigFile>,
+    /// Whether the file has been applied or has failed to load, so
+    /// that no game starts with settings that are about to change.
+    pub ready: bool,
+}
+
+pub fn load_config_file(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    source: Res<ConfigSource>,
+) {
+    commands.insert_resource(ConfigHandle {
+        handle: asset_server.load(source.path.clone()),
+        ready: false,
+    });
+}
+
+/// Replaces the [`GameConfig`] whenever the config file is loaded or
+/// changes, keeping the current one if the file is invalid.
+pub fn apply_config_file(
+    mut config_events: EventReader<AssetEvent<ConfigFile>>,
+    mut config_handle: ResMut<ConfigHandle>,
+    mut config: ResMut<GameConfig>,
+    config_files: Res<Assets<ConfigFile>>,
+    source: Res<ConfigSource>,
+    asset_server: Res<AssetServer>,
+) {
+    if !config_handle.ready
+        && asset_server.get_load_state(&config_handle.handle)
+            == LoadState::Failed
+    {
+        warn!(
+            "Could not load {:?}, using the built-in settings",
+            source.path
+        );
+        config_handle.ready = true;
+    }
+
+    for event in config_events.iter() {
+        let (AssetEvent::Created { handle }
+        | AssetEvent::Modified { handle }) = event
+        else {
+            continue;
+        };
+        if *handle != config_handle.handle {
+            continue;
+        }
+        let Some(file) = config_files.get(handle) else {
+            continue;
+        };
+
+        config_handle.ready = true;
+        match source.build(file.0.clone()) {
+            Ok((new_config, warning)) => {
+                if let Some(warning) = warning {
+                    warn!("{warning}");
+                }
+                info!(
+                    "Applied the settings in {:?}",
+                    source.path
+                );
+                *config = new_config;
+            }
+            Err(error) => {
+                error!(
+                    "Ignoring the settings in {:?}: {error}",
+                    source.path
+                );
+            }
+        }
+    }
+}
+
+/// Sizes the window and colors the background as configured.
+pub fn update_window(
+    config: Res<GameConfig>,
+    clear_color: Option<ResMut<ClearColor>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Some(mut clear_color) = clear_color {
+        clear_color.0 = config.background_color;
+    }
+    for mut window in &mut window_query {
+        if window.resolution.width() != config.screen_width
+            || window.resolution.height() != config.screen_height
+        {
+            window
+                .resolution
+                .set(config.screen_width, config.screen_height);
+        }
+    }
+}
+
+/// Reads the settings written in a config file, without checking
+/// them yet.
+pub fn parse_settings(text: &str) -> Result<Map, ConfigError> {
+    match ron::from_str(text)? {
+        Value::Map(settings) => Ok(settings),
+        _ => Err(ConfigError::NotAStruct),
+    }
+}
+
+/// Reads the settings in the file at `path`, which may not exist
+/// yet.
+pub fn read_settings(path: &Path) -> Result<Map, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse_settings(&text),
+        Err(error)
+            if error.kind() == io::ErrorKind::NotFound =>
+        {
+            Ok(Map::new())
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Replaces one setting in the settings file at `path`, creating the
+/// file and its folder if needed. Comments in the file are not kept,
+/// so this is for the user settings rather than a config file.
+pub fn save_setting(
+    path: &Path,
+    setting: &str,
+    value: Value,
+) -> Result<(), ConfigError> {
+    let mut settings = read_settings(path)?;
+    settings.insert(Value::String(setting.to_owned()), value);
+
+    let mut text = String::from("(\n");
+    for (setting, value) in settings.iter() {
+        let Value::String(setting) = setting else {
+            continue;
+        };
+        text.push_str(&format!(
+            "    {setting}: {},\n",
+            format_value(value)?
+        ));
+    }
+    text.push_str(")\n");
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Writes a value the way config files are written by hand, with
+/// nested settings as `( ... )` structs rather than maps.
+fn format_value(value: &Value) -> Result<String, ron::Error> {
+    let Value::Map(map) = value else {
+        return ron::to_string(value);
+    };
+    let mut fields = Vec::new();
+    for (key, field) in map.iter() {
+        match key {
+            Value::String(key) => fields.push(format!(
+                "{key}: {}",
+                format_value(field)?
+            )),
+            _ => return ron::to_string(value),
+        }
+    }
+    Ok(format!("({})", fields.join(", ")))
+}
+
+/// Reads a color written as a hex string, like the colors of levels.
+fn deserialize_color<'de, D>(
+    deserializer: D,
+) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(&hex).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "`{hex}` is not a hex color"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(
+        setting: &str,
+        value: &str,
+    ) -> Result<GameConfig, ConfigError> {
+        GameConfig::from_settings(
+            Map::new(),
+            &[(setting.to_owned(), value.to_owned())],
+        )
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        GameConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn overrides_replace_file_settings() {
+        let settings = parse_settings(
+            "(ball_speed: 400, paddle_speed: 300)",
+        )
+        .unwrap();
+        let config = GameConfig::from_settings(
+            settings,
+            &[("ball_speed".to_owned(), "650".to_owned())],
+        )
+        .unwrap();
+        assert_eq!(config.ball_speed, 650.0);
+        assert_eq!(config.paddle_speed, 300.0);
+    }
+
+    #[test]
+    fn colors_can_be_set_without_quotes() {
+        let config = set("background_color", "#ff8800").unwrap();
+        assert_eq!(
+            config.background_color,
+            Color::hex("ff8800").unwrap()
+        );
+        assert!(matches!(
+            set("background_color", "orange"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(matches!(
+            set("ball_sped", "650"),
+            Err(ConfigError::Invalid(_))
+        ));
+        let settings =
+            parse_settings("(no_such_setting: 1)").unwrap();
+        assert!(matches!(
+            GameConfig::from_settings(settings, &[]),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse_settings("[1, 2]"),
+            Err(ConfigError::NotAStruct)
+        ));
+    }
+
+    #[test]
+    fn values_of_the_wrong_type_are_rejected() {
+        assert!(matches!(
+            set("ball_speed", "fast"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            set("grab_cursor", "1.5"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn values_out_of_range_are_rejected() {
+        assert!(matches!(
+            set("ball_speed", "-1"),
+            Err(ConfigError::NotPositive("ball_speed"))
+        ));
+        assert!(matches!(
+            set("paddle_size", "(120, 0)"),
+            Err(ConfigError::NotPositive("paddle_size"))
+        ));
+        assert!(matches!(
+            set("mouse_smoothing", "-0.5"),
+            Err(ConfigError::Negative("mouse_smoothing"))
+        ));
+        assert!(matches!(
+            set("wall_thickness", "5000"),
+            Err(ConfigError::NoArena)
+        ));
+        assert!(matches!(
+            set("paddle_size", "(2000, 20)"),
+            Err(ConfigError::PaddleTooWide { .. })
+        ));
+        assert!(matches!(
+            set("gap_between_paddle_and_bricks", "1000"),
+            Err(ConfigError::NoRoomForBricks)
+        ));
+    }
+
+    #[test]
+    fn brick_types_can_have_sound_sets_of_their_own() {
+        let config =
+            set("sounds", "(bricks: {'3': (pitch: 0.9)})")
+                .unwrap();
+        assert_eq!(config.sounds.bricks[&'3'].pitch, 0.9);
+        assert!(matches!(
+            set("sounds", "(bricks: {'3': (pitch: 0)})"),
+            Err(ConfigError::InvalidSound { set, setting: "pitch" })
+                if set == "bricks '3'"
+        ));
+    }
+
+    #[test]
+    fn user_settings_win_over_the_file_but_not_overrides() {
+        let folder = std::env::temp_dir().join(format!(
+            "breakout-settings-{}",
+            std::process::id()
+        ));
+        let source = ConfigSource {
+            user_settings: Some(folder.join(USER_SETTINGS_FILE)),
+            overrides: vec![(
+                "paddle_speed".to_owned(),
+                "100".to_owned(),
+            )],
+            ..default()
+        };
+        let file = parse_settings(
+            "(ball_speed: 400, paddle_speed: 300, ball_radius: 5)",
+        )
+        .unwrap();
+
+        // Nothing saved yet
+        let (config, warning) =
+            source.build(file.clone()).unwrap();
+        assert_eq!(config.ball_speed, 400.0);
+        assert!(warning.is_none());
+
+        let path = source.user_settings.as_deref().unwrap();
+        for (setting, value) in
+            [("ball_speed", "700"), ("ball_radius", "8")]
+        {
+            save_setting(
+                path,
+                setting,
+                ron::from_str(value).unwrap(),
+            )
+            .unwrap();
+        }
+        save_setting(
+            path,
+            "ball_radius",
+            ron::from_str("9").unwrap(),
+        )
+        .unwrap();
+        let (config, warning) = source.build(file).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+        assert!(warning.is_none());
+
+        assert_eq!(config.ball_speed, 700.0);
+        assert_eq!(config.ball_radius, 9.0);
+        assert_eq!(config.paddle_speed, 100.0);
+    }
+
+    /// Writes `text` as the user settings in a folder of its own, as
+    /// tests run in parallel.
+    fn user_settings(test: &str, text: &str) -> ConfigSource {
+        let folder = std::env::temp_dir().join(format!(
+            "breakout-{test}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        let path = folder.join(USER_SETTINGS_FILE);
+        fs::write(&path, text).unwrap();
+        ConfigSource {
+            user_settings: Some(path),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn unusable_user_settings_are_set_aside() {
+        for (test, text) in [
+            ("garbled-settings", "(ball_speed: 7"),
+            ("stale-settings", "(ball_sped: 700)"),
+            ("invalid-settings", "(ball_speed: -700)"),
+        ] {
+            let source = user_settings(test, text);
+            let path = source.user_settings.as_deref().unwrap();
+            let file =
+                parse_settings("(ball_speed: 400)").unwrap();
+
+            let (config, warning) = source.build(file).unwrap();
+            assert_eq!(config.ball_speed, 400.0);
+            assert!(warning.is_some());
+            assert!(!path.exists());
+            let backup = path.with_extension("ron.corrupt");
+            assert_eq!(
+                fs::read_to_string(backup).unwrap(),
+                text
+            );
+            fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn config_file_errors_leave_the_user_settings_alone() {
+        let source =
+            user_settings("file-errors", "(ball_radius: 8)");
+        let path = source.user_settings.as_deref().unwrap();
+        let file = parse_settings("(ball_speed: -1)").unwrap();
+
+        assert!(matches!(
+            source.build(file),
+            Err(ConfigError::NotPositive("ball_speed"))
+        ));
+        assert!(path.exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn thick_walls_leave_no_room_for_bricks() {
+        // The screen is wide enough for a brick and the side gaps,
+        // but not once the walls take their share of it
+        let config = GameConfig {
+            screen_width: 400.0,
+            wall_thickness: 150.0,
+            gap_between_bricks_and_sides: 50.0,
+            brick_size: Vec2::new(160.0, 50.0),
+            ..default()
+        };
+        assert!(
+            config.screen_width
+                - 2.0 * config.gap_between_bricks_and_sides
+                >= config.brick_size.x
+        );
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::NoRoomForBricks)
+        ));
+    }
+}
@@ -0,0 +1,634 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    components::{
+        ball::{
+            follow_paddle, launch_stuck_balls,
+            setup_ball_assets, update_ball_assets, Ball,
+            BallAssets, BallSpeed, ServedBallBundle,
+        },
+        brick::Brick,
+        paddle::{move_paddle, update_paddle, Paddle},
+        wall::{
+            update_walls, KillZone, KillZoneBundle, WallBundle,
+            WallLocation,
+        },
+    },
+    config::GameConfig,
+    input::PaddleIntent,
+    level::{
+        spawn_level, CurrentLevel, Level, Levels, StartingLevel,
+    },
+    physics::{Collider, TIME_STEP},
+    powerup::{ActiveEffects, PowerUp},
+    score::LevelClock,
+    ui::ScoreBoard,
+};
+
+pub const STARTING_LIVES: usize = 3;
+// Seconds between losing a ball and serving the next one
+pub const SERVE_DELAY: f32 = 1.0;
+
+#[derive(
+    States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash,
+)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Playing,
+    Paused,
+    Controls,
+    HighScores,
+    LevelCleared,
+    GameOver,
+}
+
+/// Every system that runs a fixed step of play, which only happens
+/// in [`GameState::Playing`].
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// Stages of a fixed step of play, run one after the other so that
+/// the same inputs always lead to the same game.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameplayStage {
+    /// Decides what the paddle does this step.
+    Input,
+    /// Captures the decided input.
+    Record,
+    /// Applies the active power-ups to the paddle and balls.
+    Effects,
+    /// Moves the paddle and everything that isn't a ball.
+    Movement,
+    /// Moves the balls, bouncing them off whatever they hit.
+    Collision,
+    /// Loses lives, serves balls and ends levels.
+    Rules,
+    /// Drops, collects and expires power-ups.
+    PowerUps,
+}
+
+/// The game's states, rules and session, from serving the ball to
+/// moving through the levels.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .insert_resource(Lives {
+                remaining: STARTING_LIVES,
+            })
+            .init_resource::<GameSeed>()
+            .init_resource::<PaddleIntent>()
+            .add_event::<LifeLostEvent>()
+            .add_event::<RestartLevelEvent>()
+            .add_event::<LevelClearedEvent>()
+            // Configure how frequently our gameplay systems are run
+            .insert_resource(FixedTime::new_from_secs(TIME_STEP))
+            .configure_set(
+                FixedUpdate,
+                GameplaySet
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(no_state_change_pending),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    GameplayStage::Input,
+                    GameplayStage::Record,
+                    GameplayStage::Effects,
+                    GameplayStage::Movement,
+                    GameplayStage::Collision,
+                    GameplayStage::Rules,
+                    GameplayStage::PowerUps,
+                )
+                    .chain()
+                    .in_set(GameplaySet),
+            )
+            .add_systems(Startup, setup_ball_assets)
+            // Follow changes to the config file while playing
+            .add_systems(
+                Update,
+                (
+                    update_walls,
+                    update_paddle,
+                    update_ball_assets,
+                )
+                    .run_if(resource_changed::<GameConfig>()),
+            )
+            // Tear down any session left over from a previous run
+            // before showing the menu again
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (despawn_screen::<InGame>, unpause_time),
+            )
+            // Stop the clock while paused, so that no time builds up
+            // for the fixed step to catch up on when play resumes
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            // Resuming from the pause screen re-enters `Playing` with
+            // the session still alive, so only spawn a new one if
+            // needed
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    // The key that left a menu may also be the one
+                    // that launches balls
+                    reset_paddle_intent,
+                    unpause_time,
+                    setup_game.run_if(not(
+                        any_with_component::<Paddle>(),
+                    )),
+                    restart_level,
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::LevelCleared),
+                start_next_level,
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                despawn_screen::<InGame>,
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        move_paddle,
+                        follow_paddle,
+                        launch_stuck_balls,
+                    )
+                        .chain()
+                        .in_set(GameplayStage::Movement),
+                    (
+                        check_for_ball_loss,
+                        serve_ball,
+                        check_for_level_cleared,
+                    )
+                        .chain()
+                        .in_set(GameplayStage::Rules),
+                ),
+            );
+    }
+}
+
+/// Marks every entity that belongs to a running game, so the whole
+/// session can be torn down when leaving it.
+#[derive(Component)]
+pub struct InGame;
+
+/// Balls the player can still lose before the game is over.
+#[derive(Resource)]
+pub struct Lives {
+    pub remaining: usize,
+}
+
+/// Counts down to the next serve after a ball has been lost.
+#[derive(Resource)]
+pub struct ServeTimer(pub Timer);
+
+/// Sent when the last ball in play is lost.
+#[derive(Event, Default)]
+pub struct LifeLostEvent;
+
+/// Sent when the last breakable brick of a level is gone, including
+/// the last level's.
+#[derive(Event, Default)]
+pub struct LevelClearedEvent;
+
+/// Asks for the current level to start over from how it was when
+/// it began, as the game returns to [`GameState::Playing`].
+#[derive(Event, Default)]
+pub struct RestartLevelEvent;
+
+/// The score and lives at the start of the current level, for
+/// restarting it.
+#[derive(Resource)]
+pub struct LevelCheckpoint {
+    pub score: usize,
+    pub lives: usize,
+}
+
+/// Seed for the [`GameRng`] of every new game. Without one, each game
+/// draws a fresh seed.
+#[derive(Resource, Default)]
+pub struct GameSeed(pub Option<u64>);
+
+/// Source of every random decision made during play.
+#[derive(Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random())
+    }
+}
+
+impl Deref for GameRng {
+    type Target = ChaCha8Rng;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
+}
+
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
+    }
+}
+
+/// Stops the game from carrying on for the rest of a frame once it
+/// has decided to leave [`GameState::Playing`], so that every fixed
+/// step that runs is one that a replay can reproduce.
+pub fn no_state_change_pending(
+    next_state: Res<NextState<GameState>>,
+) -> bool {
+    next_state.0.is_none()
+}
+
+pub fn check_for_ball_loss(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut life_lost_events: EventWriter<LifeLostEvent>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+    kill_zone_query: Query<&Transform, With<KillZone>>,
+) {
+    let kill_zone_transform = kill_zone_query.single();
+    let kill_zone_top = kill_zone_transform.translation.y
+        + kill_zone_transform.scale.y / 2.0;
+
+    let mut balls_in_play = 0;
+    for (ball_entity, ball_transform) in &ball_query {
+        // A ball is lost once it has fully dropped past the floor
+        let ball_top = ball_transform.translation.y
+            + ball_transform.scale.y / 2.0;
+        if ball_top > kill_zone_top {
+            balls_in_play += 1;
+        } else {
+            commands.entity(ball_entity).despawn();
+        }
+    }
+
+    // A life is only lost with the last ball. While waiting for a serve
+    // there are no balls at all, and nothing to lose.
+    if balls_in_play > 0 || ball_query.is_empty() {
+        return;
+    }
+
+    lives.remaining = lives.remaining.saturating_sub(1);
+    life_lost_events.send_default();
+    if lives.remaining > 0 {
+        commands.insert_resource(ServeTimer(
+            Timer::from_seconds(SERVE_DELAY, TimerMode::Once),
+        ));
+    } else {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub fn serve_ball(
+    mut commands: Commands,
+    serve_timer: Option<ResMut<ServeTimer>>,
+    ball_assets: Res<BallAssets>,
+    time_step: Res<FixedTime>,
+    config: Res<GameConfig>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+) {
+    let Some(mut serve_timer) = serve_timer else {
+        return;
+    };
+
+    if serve_timer.0.tick(time_step.period).just_finished() {
+        commands.remove_resource::<ServeTimer>();
+        commands.spawn((
+            ServedBallBundle::new(
+                &ball_assets,
+                paddle_query.single().translation.x,
+                &config,
+            ),
+            InGame,
+        ));
+    }
+}
+
+pub fn reset_paddle_intent(mut intent: ResMut<PaddleIntent>) {
+    *intent = PaddleIntent::default();
+}
+
+pub fn check_for_level_cleared(
+    brick_query: Query<&Brick>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    lives: Res<Lives>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut level_cleared_events: EventWriter<LevelClearedEvent>,
+) {
+    // Steel bricks can't be broken, so they don't need to be
+    if brick_query.iter().any(Brick::is_breakable) {
+        return;
+    }
+    // The last ball dropped in the same step, which has already
+    // ended the game
+    if lives.remaining == 0 {
+        return;
+    }
+
+    level_cleared_events.send_default();
+
+    // Clearing the last level ends the run with the player's lives
+    // still intact, which the game over screen reports as a win
+    if levels.is_last(current_level.0) {
+        next_state.set(GameState::GameOver);
+    } else {
+        next_state.set(GameState::LevelCleared);
+    }
+}
+
+/// Starts a new game on the starting level, with a fresh paddle,
+/// scoreboard and arena.
+#[allow(clippy::too_many_arguments)]
+pub fn setup_game(
+    mut commands: Commands,
+    ball_assets: Res<BallAssets>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    game_seed: Res<GameSeed>,
+    starting_level: Res<StartingLevel>,
+    config: Res<GameConfig>,
+    mut scoreboard: ResMut<ScoreBoard>,
+    mut lives: ResMut<Lives>,
+) {
+    *scoreboard = ScoreBoard::default();
+    lives.remaining = STARTING_LIVES;
+    commands.insert_resource(LevelCheckpoint {
+        score: scoreboard.score,
+        lives: lives.remaining,
+    });
+    commands.insert_resource(GameRng::new(
+        game_seed.0.unwrap_or_else(rand::random),
+    ));
+
+    // Paddle
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(
+                    0.0,
+                    config.paddle_y(),
+                    0.0,
+                ),
+                scale: config.paddle_size.extend(0.0),
+                ..default()
+            },
+            sprite: Sprite {
+                color: config.paddle_color,
+                ..default()
+            },
+            ..default()
+        },
+        Paddle::default(),
+        Collider,
+        InGame,
+    ));
+
+    // Wall
+    for location in [
+        WallLocation::Top,
+        WallLocation::Left,
+        WallLocation::Right,
+    ] {
+        commands
+            .spawn((WallBundle::new(location, &config), InGame));
+    }
+    commands.spawn((KillZoneBundle::new(&config), InGame));
+
+    // Ball and bricks
+    let index = if starting_level.0 < levels.0.len() {
+        starting_level.0
+    } else {
+        warn!(
+            "There is no level {}, starting on the first one",
+            starting_level.0 + 1
+        );
+        0
+    };
+    let level = level_assets
+        .get(&levels.0[index])
+        .expect("levels are loaded before leaving the menu");
+    start_level(
+        &mut commands,
+        index,
+        level,
+        &ball_assets,
+        0.0,
+        &config,
+    );
+}
+
+/// Spawns the level at `index` together with a ball served on the
+/// paddle at `paddle_x`.
+pub fn start_level(
+    commands: &mut Commands,
+    index: usize,
+    level: &Level,
+    ball_assets: &BallAssets,
+    paddle_x: f32,
+    config: &GameConfig,
+) {
+    let ball_speed = level.ball_speed(index, config);
+    info!("Starting level {}: {}", index + 1, level.name);
+
+    commands.insert_resource(CurrentLevel(index));
+    commands.insert_resource(BallSpeed(ball_speed));
+    commands.insert_resource(LevelClock::default());
+    commands.remove_resource::<ServeTimer>();
+    commands.insert_resource(ActiveEffects::default());
+    commands.spawn((
+        ServedBallBundle::new(ball_assets, paddle_x, config),
+        InGame,
+    ));
+    spawn_level(commands, level, config);
+}
+
+/// Replaces the cleared level with the next one, keeping the paddle,
+/// score and lives.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn start_next_level(
+    mut commands: Commands,
+    scoreboard: Res<ScoreBoard>,
+    lives: Res<Lives>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    ball_assets: Res<BallAssets>,
+    config: Res<GameConfig>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    leftover_query: Query<
+        Entity,
+        Or<(With<Ball>, With<Brick>, With<PowerUp>)>,
+    >,
+) {
+    for entity in &leftover_query {
+        commands.entity(entity).despawn();
+    }
+
+    let index = current_level.0 + 1;
+    let level = level_assets
+        .get(&levels.0[index])
+        .expect("levels are loaded before leaving the menu");
+    commands.insert_resource(LevelCheckpoint {
+        score: scoreboard.score,
+        lives: lives.remaining,
+    });
+    start_level(
+        &mut commands,
+        index,
+        level,
+        &ball_assets,
+        paddle_query.single().translation.x,
+        &config,
+    );
+}
+
+/// Puts the current level back the way it started, along with the
+/// score and lives, keeping the paddle where it is.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn restart_level(
+    mut commands: Commands,
+    mut restart_level_events: EventReader<RestartLevelEvent>,
+    checkpoint: Option<Res<LevelCheckpoint>>,
+    mut scoreboard: ResMut<ScoreBoard>,
+    mut lives: ResMut<Lives>,
+    current_level: Option<Res<CurrentLevel>>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    ball_assets: Res<BallAssets>,
+    config: Res<GameConfig>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    leftover_query: Query<
+        Entity,
+        Or<(With<Ball>, With<Brick>, With<PowerUp>)>,
+    >,
+) {
+    if restart_level_events.iter().count() == 0 {
+        return;
+    }
+    let (Some(checkpoint), Some(current_level), Ok(paddle)) =
+        (checkpoint, current_level, paddle_query.get_single())
+    else {
+        return;
+    };
+
+    for entity in &leftover_query {
+        commands.entity(entity).despawn();
+    }
+    *scoreboard = ScoreBoard {
+        score: checkpoint.score,
+        ..default()
+    };
+    lives.remaining = checkpoint.lives;
+
+    let index = current_level.0;
+    let level = level_assets
+        .get(&levels.0[index])
+        .expect("levels are loaded before leaving the menu");
+    start_level(
+        &mut commands,
+        index,
+        level,
+        &ball_assets,
+        paddle.translation.x,
+        &config,
+    );
+}
+
+pub fn pause_time(mut time: ResMut<Time>) {
+    time.pause();
+}
+
+pub fn unpause_time(mut time: ResMut<Time>) {
+    time.unpause();
+}
+
+pub fn despawn_screen<T: Component>(
+    mut commands: Commands,
+    to_despawn: Query<Entity, With<T>>,
+) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first of two levels with its last brick just broken, as its
+    /// only ball drops past the floor.
+    fn last_step_of_level(lives: usize) -> App {
+        let mut app = App::new();
+        app.add_state::<GameState>()
+            .add_event::<LifeLostEvent>()
+            .add_event::<LevelClearedEvent>()
+            .insert_resource(Lives { remaining: lives })
+            .insert_resource(Levels(vec![Handle::default(); 2]))
+            .insert_resource(CurrentLevel(0))
+            .add_systems(
+                Update,
+                (check_for_ball_loss, check_for_level_cleared)
+                    .chain(),
+            );
+        app.world.spawn((Transform::default(), KillZone));
+        app.world.spawn((
+            Transform::from_xyz(0.0, -100.0, 0.0),
+            Ball,
+        ));
+        app
+    }
+
+    fn next_state(app: &App) -> Option<GameState> {
+        app.world.resource::<NextState<GameState>>().0
+    }
+
+    fn levels_cleared(app: &App) -> usize {
+        app.world.resource::<Events<LevelClearedEvent>>().len()
+    }
+
+    #[test]
+    fn losing_the_last_life_as_the_level_clears_ends_the_game() {
+        let mut app = last_step_of_level(1);
+        app.update();
+        assert_eq!(app.world.resource::<Lives>().remaining, 0);
+        assert_eq!(next_state(&app), Some(GameState::GameOver));
+        assert_eq!(levels_cleared(&app), 0);
+    }
+
+    #[test]
+    fn losing_a_ball_as_the_level_clears_still_clears_it() {
+        let mut app = last_step_of_level(2);
+        app.update();
+        assert_eq!(app.world.resource::<Lives>().remaining, 1);
+        assert_eq!(
+            next_state(&app),
+            Some(GameState::LevelCleared)
+        );
+        assert_eq!(levels_cleared(&app), 1);
+    }
+}
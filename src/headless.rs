@@ -0,0 +1,140 @@
+use bevy::{
+    app::{AppExit, PluginGroupBuilder},
+    input::InputPlugin,
+    log::LogPlugin,
+    prelude::*,
+};
+
+use crate::{
+    components::{ball::Ball, paddle::Paddle},
+    config::GameConfig,
+    game::Lives,
+    input::PaddleIntent,
+    level::CurrentLevel,
+    physics::Velocity,
+    ui::ScoreBoard,
+};
+
+// An hour of play at the fixed timestep
+pub const DEFAULT_MAX_STEPS: u64 = 60 * 60 * 60;
+// Fraction of the paddle's half width the bot hits the ball off
+// center by, so the ball doesn't settle into a vertical loop
+pub const BOT_AIM_OFFSET: f32 = 0.4;
+
+/// Progress of a run without a window.
+#[derive(Resource)]
+pub struct HeadlessRun {
+    pub steps: u64,
+    pub max_steps: u64,
+}
+
+impl HeadlessRun {
+    pub fn new(max_steps: u64) -> Self {
+        HeadlessRun {
+            steps: 0,
+            max_steps,
+        }
+    }
+}
+
+/// Everything the game needs to run without a window, a GPU or an
+/// audio device. The schedule runner updates as fast as it can.
+pub fn headless_plugins() -> PluginGroupBuilder {
+    MinimalPlugins
+        .build()
+        .add(LogPlugin::default())
+        .add(AssetPlugin::default())
+        .add(InputPlugin)
+}
+
+/// Plays the game by keeping the paddle under the ball that is about
+/// to reach it first, launching any ball it catches straight away.
+pub fn bot_paddle_intent(
+    mut intent: ResMut<PaddleIntent>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    time_step: Res<FixedTime>,
+    config: Res<GameConfig>,
+) {
+    let paddle_transform = paddle_query.single();
+
+    // Falling balls come first, then the lowest one
+    let target = ball_query.iter().min_by(|(a, va), (b, vb)| {
+        (va.y >= 0.0)
+            .cmp(&(vb.y >= 0.0))
+            .then(a.translation.y.total_cmp(&b.translation.y))
+    });
+
+    let target_x = match target {
+        // Meet the ball with the side it is coming from, sending it
+        // back the way it came
+        Some((transform, velocity)) => {
+            transform.translation.x
+                + velocity.x.signum()
+                    * BOT_AIM_OFFSET
+                    * paddle_transform.scale.x
+                    / 2.0
+        }
+        None => 0.0,
+    };
+
+    let max_step =
+        config.paddle_speed * time_step.period.as_secs_f32();
+    intent.direction =
+        ((target_x - paddle_transform.translation.x) / max_step)
+            .clamp(-1.0, 1.0);
+    intent.launch = true;
+}
+
+pub fn count_headless_steps(
+    mut run: ResMut<HeadlessRun>,
+    scoreboard: Res<ScoreBoard>,
+    lives: Res<Lives>,
+    current_level: Option<Res<CurrentLevel>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    run.steps += 1;
+    if run.steps >= run.max_steps {
+        println!("Stopped after reaching the step limit");
+        report(
+            &run,
+            &scoreboard,
+            &lives,
+            current_level.as_deref(),
+        );
+        app_exit_events.send(AppExit);
+    }
+}
+
+pub fn finish_headless_run(
+    run: Res<HeadlessRun>,
+    scoreboard: Res<ScoreBoard>,
+    lives: Res<Lives>,
+    current_level: Option<Res<CurrentLevel>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    println!(
+        "{}",
+        if lives.remaining > 0 {
+            "You Win!"
+        } else {
+            "Game Over"
+        }
+    );
+    report(&run, &scoreboard, &lives, current_level.as_deref());
+    app_exit_events.send(AppExit);
+}
+
+fn report(
+    run: &HeadlessRun,
+    scoreboard: &ScoreBoard,
+    lives: &Lives,
+    current_level: Option<&CurrentLevel>,
+) {
+    println!("Score: {}", scoreboard.score);
+    println!("Lives: {}", lives.remaining);
+    if let Some(current_level) = current_level {
+        println!("Level: {}", current_level.0 + 1);
+    }
+    println!("Steps: {}", run.steps);
+}
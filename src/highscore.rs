@@ -0,0 +1,515 @@
+use std::{
+    cmp::Reverse,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+use ron::Value;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    game::GameState,
+    level::{CurrentLevel, StartingLevel},
+    ui::ScoreBoard,
+};
+
+// How many runs the table keeps
+pub const HIGH_SCORE_COUNT: usize = 10;
+pub const INITIALS_LENGTH: usize = 3;
+pub const HIGH_SCORE_FILE: &str = "high_scores.ron";
+// Folder of the game inside the user's data directory
+pub const DATA_FOLDER: &str = "breakout";
+pub const SAVE_INITIALS_KEY: KeyCode = KeyCode::Return;
+pub const ERASE_INITIAL_KEY: KeyCode = KeyCode::Back;
+
+/// Keeps the best runs in a file in the user's data directory, asking
+/// for the player's initials when a game ends with one of them.
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InitialsEntry>()
+            .add_systems(Startup, load_high_scores)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                start_initials_entry,
+            )
+            .add_systems(
+                Update,
+                type_initials
+                    .run_if(in_state(GameState::GameOver)),
+            );
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HighScoreError {
+    #[error("could not access the high score file: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not write the high scores: {0}")]
+    Write(#[from] ron::Error),
+    #[error(
+        "there is no data directory to keep high scores in"
+    )]
+    NoDataDir,
+}
+
+/// How a run was played, as runs started on a later level can't be
+/// compared with full ones. Written as a string, since each run is
+/// read on its own through a RON `Value`, which can't hold enums.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(into = "String", try_from = "String")]
+pub enum GameMode {
+    /// Started on the first level.
+    Campaign,
+    /// Started on a later level.
+    Practice,
+}
+
+impl GameMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Campaign => "Campaign",
+            GameMode::Practice => "Practice",
+        }
+    }
+}
+
+impl From<GameMode> for String {
+    fn from(mode: GameMode) -> Self {
+        mode.label().to_owned()
+    }
+}
+
+impl TryFrom<String> for GameMode {
+    type Error = String;
+
+    fn try_from(label: String) -> Result<Self, Self::Error> {
+        [GameMode::Campaign, GameMode::Practice]
+            .into_iter()
+            .find(|mode| mode.label() == label)
+            .ok_or_else(|| {
+                format!("unknown game mode `{label}`")
+            })
+    }
+}
+
+/// One run in the high score table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: usize,
+    /// Number of the last level played, counting from 1.
+    pub level: usize,
+    /// Day the run ended, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub mode: GameMode,
+}
+
+/// The best runs, highest score first.
+#[derive(Resource, Debug, Default)]
+pub struct HighScores {
+    /// File the table is kept in, if there is anywhere to keep it.
+    pub path: Option<PathBuf>,
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Reads the table from `path`, starting an empty one if there is
+    /// no file. Runs that can't be read are dropped, and a file that
+    /// can't be read at all is set aside rather than overwritten.
+    pub fn load(path: PathBuf) -> HighScores {
+        let mut high_scores = HighScores {
+            path: Some(path.clone()),
+            entries: Vec::new(),
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error)
+                if error.kind() == io::ErrorKind::NotFound =>
+            {
+                return high_scores;
+            }
+            Err(error) => {
+                warn!("Could not read {path:?}: {error}");
+                high_scores.path = None;
+                return high_scores;
+            }
+        };
+
+        let entries = match ron::from_str(&text) {
+            Ok(Value::Seq(entries)) => entries,
+            Ok(_) => {
+                set_aside(
+                    &path,
+                    "it does not hold a list of runs",
+                );
+                return high_scores;
+            }
+            Err(error) => {
+                set_aside(&path, &error.to_string());
+                return high_scores;
+            }
+        };
+        for entry in entries {
+            match entry.into_rust::<HighScore>() {
+                Ok(entry) => high_scores.entries.push(entry),
+                Err(error) => {
+                    warn!(
+                        "Dropping a run from {path:?}: {error}"
+                    );
+                }
+            }
+        }
+
+        high_scores
+            .entries
+            .sort_by_key(|entry| Reverse(entry.score));
+        high_scores.entries.truncate(HIGH_SCORE_COUNT);
+        high_scores
+    }
+
+    /// Whether a run scoring `score` makes it into the table.
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < HIGH_SCORE_COUNT
+                || self
+                    .entries
+                    .last()
+                    .is_some_and(|last| score > last.score))
+    }
+
+    /// Adds a run below any with the same score, returning its rank
+    /// counting from 0 if it made it into the table.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        if rank >= HIGH_SCORE_COUNT {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(HIGH_SCORE_COUNT);
+        Some(rank)
+    }
+
+    /// Writes the table to its file, through a temporary file so that
+    /// a crash can't leave half of it behind.
+    pub fn save(&self) -> Result<(), HighScoreError> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or(HighScoreError::NoDataDir)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(
+            &self.entries,
+            ron::ser::PrettyConfig::default(),
+        )?;
+        let temporary = path.with_extension("ron.tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Moves an unreadable high score file out of the way, keeping it for
+/// whoever wants to recover it.
+fn set_aside(path: &Path, reason: &str) {
+    let backup = path.with_extension("ron.corrupt");
+    match fs::rename(path, &backup) {
+        Ok(()) => warn!(
+            "Could not read the high scores, as {reason}. Moved them \
+             to {backup:?} and started a new table"
+        ),
+        Err(error) => warn!(
+            "Could not read the high scores, as {reason}, nor move \
+             them to {backup:?}: {error}"
+        ),
+    }
+}
+
+/// The directory each platform keeps user data in.
+pub fn data_dir() -> Option<PathBuf> {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home()
+            .map(|home| home.join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| {
+                home().map(|home| home.join(".local/share"))
+            })
+    }
+}
+
+/// Today's date as `YYYY-MM-DD` in UTC.
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400)
+        as i64;
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's
+    // `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460
+        + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year = day_of_era
+        - (365 * year_of_era + year_of_era / 4
+            - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// A run waiting for the player's initials before it goes into the
+/// high score table.
+#[derive(Resource, Default)]
+pub struct InitialsEntry {
+    pub pending: Option<HighScore>,
+    pub initials: String,
+}
+
+pub fn load_high_scores(mut commands: Commands) {
+    let high_scores = match data_dir() {
+        Some(dir) => HighScores::load(
+            dir.join(DATA_FOLDER).join(HIGH_SCORE_FILE),
+        ),
+        None => {
+            warn!(
+                "No data directory, high scores won't be kept"
+            );
+            HighScores::default()
+        }
+    };
+    commands.insert_resource(high_scores);
+}
+
+/// Asks for the player's initials if the game that just ended made it
+/// into the high score table.
+pub fn start_initials_entry(
+    high_scores: Res<HighScores>,
+    scoreboard: Res<ScoreBoard>,
+    current_level: Res<CurrentLevel>,
+    starting_level: Res<StartingLevel>,
+    mut entry: ResMut<InitialsEntry>,
+) {
+    entry.initials.clear();
+    entry.pending = high_scores
+        .qualifies(scoreboard.score)
+        .then(|| HighScore {
+            name: String::new(),
+            score: scoreboard.score,
+            level: current_level.0 + 1,
+            date: today(),
+            mode: if starting_level.0 == 0 {
+                GameMode::Campaign
+            } else {
+                GameMode::Practice
+            },
+        });
+}
+
+/// Takes letters and digits typed for the initials, and saves the run
+/// once they are confirmed.
+pub fn type_initials(
+    mut character_events: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    mut entry: ResMut<InitialsEntry>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if entry.pending.is_none() {
+        character_events.clear();
+        return;
+    }
+
+    for event in character_events.iter() {
+        if event.char.is_ascii_alphanumeric()
+            && entry.initials.len() < INITIALS_LENGTH
+        {
+            entry.initials.push(event.char.to_ascii_uppercase());
+        }
+    }
+    if keyboard.just_pressed(ERASE_INITIAL_KEY) {
+        entry.initials.pop();
+    }
+
+    if !keyboard.just_pressed(SAVE_INITIALS_KEY)
+        || entry.initials.is_empty()
+    {
+        return;
+    }
+    if let Some(mut high_score) = entry.pending.take() {
+        high_score.name = entry.initials.clone();
+        high_scores.insert(high_score);
+        match high_scores.save() {
+            Ok(()) => info!(
+                "Saved the high scores to {:?}",
+                high_scores.path
+            ),
+            Err(error) => {
+                error!("Failed to save the high scores: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A folder of its own for each test, as tests run in parallel.
+    fn temp_folder(test: &str) -> PathBuf {
+        let folder = env::temp_dir().join(format!(
+            "breakout-{test}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    fn run(name: &str, score: usize) -> HighScore {
+        HighScore {
+            name: name.to_owned(),
+            score,
+            level: 1,
+            date: "2024-01-01".to_owned(),
+            mode: GameMode::Campaign,
+        }
+    }
+
+    fn names(high_scores: &HighScores) -> Vec<&str> {
+        high_scores
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn missing_file_starts_an_empty_table() {
+        let folder = temp_folder("missing");
+        let path = folder.join(HIGH_SCORE_FILE);
+        let high_scores = HighScores::load(path.clone());
+        assert!(high_scores.entries.is_empty());
+        assert_eq!(high_scores.path, Some(path));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn malformed_runs_are_dropped() {
+        let folder = temp_folder("malformed");
+        let path = folder.join(HIGH_SCORE_FILE);
+        fs::write(
+            &path,
+            r#"[
+                (name: "AAA", score: 100, level: 2,
+                 date: "2024-01-01", mode: "Campaign"),
+                (name: "BBB", score: "lots", level: 1,
+                 date: "2024-01-01", mode: "Campaign"),
+                (name: "CCC", score: 300, level: 3,
+                 date: "2024-01-02", mode: "Arcade"),
+                (name: "DDD", score: 200,
+                 date: "2024-01-03", mode: "Practice"),
+                (name: "EEE", score: 150, level: 1,
+                 date: "2024-01-04", mode: "Practice"),
+            ]"#,
+        )
+        .unwrap();
+
+        let high_scores = HighScores::load(path.clone());
+        assert_eq!(names(&high_scores), ["EEE", "AAA"]);
+        // The rest of the file was fine, so it stays where it is
+        assert!(path.exists());
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn unreadable_file_is_set_aside() {
+        for (test, text) in [
+            ("garbled", "[(name: \"AAA\", score: 1"),
+            ("not-a-list", "(name: \"AAA\", score: 100)"),
+        ] {
+            let folder = temp_folder(test);
+            let path = folder.join(HIGH_SCORE_FILE);
+            fs::write(&path, text).unwrap();
+
+            let high_scores = HighScores::load(path.clone());
+            assert!(high_scores.entries.is_empty());
+            assert!(!path.exists());
+            assert_eq!(
+                fs::read_to_string(
+                    folder.join("high_scores.ron.corrupt")
+                )
+                .unwrap(),
+                text
+            );
+            fs::remove_dir_all(folder).unwrap();
+        }
+    }
+
+    #[test]
+    fn table_keeps_the_best_runs() {
+        let mut high_scores = HighScores::default();
+        for score in
+            [50, 120, 10, 90, 30, 110, 70, 20, 100, 60, 40, 80]
+        {
+            high_scores.insert(run(&score.to_string(), score));
+        }
+        assert_eq!(high_scores.entries.len(), HIGH_SCORE_COUNT);
+        assert_eq!(high_scores.entries[0].score, 120);
+        assert_eq!(high_scores.entries[9].score, 30);
+
+        assert!(!high_scores.qualifies(30));
+        assert_eq!(high_scores.insert(run("low", 30)), None);
+        assert!(high_scores.qualifies(31));
+        // A tie goes below the run that got there first
+        assert_eq!(high_scores.insert(run("tie", 100)), Some(3));
+        assert_eq!(high_scores.entries[2].name, "100");
+        assert_eq!(high_scores.entries[9].score, 40);
+    }
+
+    #[test]
+    fn saved_table_is_cut_to_the_best_runs_on_load() {
+        let folder = temp_folder("saved");
+        let path = folder.join(HIGH_SCORE_FILE);
+        let high_scores = HighScores {
+            path: Some(path.clone()),
+            entries: (1..=12)
+                .map(|score| run(&score.to_string(), score))
+                .collect(),
+        };
+        high_scores.save().unwrap();
+
+        let loaded = HighScores::load(path);
+        assert_eq!(loaded.entries.len(), HIGH_SCORE_COUNT);
+        assert_eq!(loaded.entries[0], run("12", 12));
+        assert_eq!(loaded.entries[9], run("3", 3));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
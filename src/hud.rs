@@ -0,0 +1,386 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    game::{GameState, InGame, Lives},
+    highscore::HighScores,
+    level::CurrentLevel,
+    score::{combo_multiplier, ScoreEvent},
+    ui::ScoreBoard,
+};
+
+pub const HUD_FONT_SIZE: f32 = 40.0;
+pub const HUD_PADDING: UiRect = UiRect::all(Val::Px(5.0));
+pub const HUD_GAP: Val = Val::Px(30.0);
+pub const LIFE_ICON_SIZE: Val = Val::Px(20.0);
+pub const LIFE_ICON_GAP: Val = Val::Px(6.0);
+// Fewest bricks hit in a row worth showing as a combo
+pub const MIN_COMBO_SHOWN: u32 = 2;
+pub const POPUP_FONT_SIZE: f32 = 28.0;
+pub const POPUP_RISE_SPEED: f32 = 60.0;
+// Seconds a score popup takes to fade out
+pub const POPUP_LIFETIME: f32 = 1.0;
+// Space between bonus popups shown together
+pub const POPUP_STACK_GAP: f32 = 40.0;
+// Draws popups in front of the balls
+pub const POPUP_Z: f32 = 2.0;
+
+/// Shows the score, lives, level, best score and combo along the top
+/// of the screen while a game is on, and pops up the points as they
+/// are scored.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            setup_hud.run_if(not(any_with_component::<Hud>())),
+        )
+        .add_systems(
+            Update,
+            (
+                update_score_text,
+                update_level_text,
+                update_best_text,
+                update_combo_text,
+                update_life_icons,
+                spawn_score_popups,
+                animate_score_popups,
+                update_hud_colors
+                    .run_if(resource_changed::<GameConfig>()),
+            ),
+        );
+    }
+}
+
+/// The bar holding every widget of the HUD.
+#[derive(Component)]
+pub struct Hud;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Component)]
+pub struct LevelText;
+
+/// Best score so far, counting the current game.
+#[derive(Component)]
+pub struct BestText;
+
+/// Bricks hit in a row and the multiplier the next one broken will
+/// score with, hidden while there is no combo.
+#[derive(Component)]
+pub struct ComboText;
+
+/// Holds one [`LifeIcon`] per life left.
+#[derive(Component)]
+pub struct LifeIcons;
+
+#[derive(Component)]
+pub struct LifeIcon;
+
+/// Points just scored, rising and fading out where they were scored.
+#[derive(Component)]
+pub struct ScorePopup {
+    pub timer: Timer,
+}
+
+/// A label and a value, colored as text and score. The values are
+/// filled in by the update systems once the widgets exist.
+fn labeled_text(label: &str, config: &GameConfig) -> TextBundle {
+    TextBundle::from_sections([
+        TextSection::new(
+            label,
+            TextStyle {
+                font_size: HUD_FONT_SIZE,
+                color: config.text_color,
+                ..default()
+            },
+        ),
+        TextSection::from_style(TextStyle {
+            font_size: HUD_FONT_SIZE,
+            color: config.score_color,
+            ..default()
+        }),
+    ])
+}
+
+fn life_icon(config: &GameConfig) -> impl Bundle {
+    (
+        NodeBundle {
+            style: Style {
+                width: LIFE_ICON_SIZE,
+                height: LIFE_ICON_SIZE,
+                ..default()
+            },
+            background_color: config.ball_color.into(),
+            ..default()
+        },
+        LifeIcon,
+    )
+}
+
+pub fn setup_hud(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    padding: HUD_PADDING,
+                    justify_content:
+                        JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Hud,
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: HUD_GAP,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        labeled_text("Score: ", &config),
+                        ScoreText,
+                    ));
+                    parent.spawn((
+                        labeled_text("Combo ", &config),
+                        ComboText,
+                    ));
+                });
+            parent.spawn((
+                labeled_text("Level ", &config),
+                LevelText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: HUD_GAP,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        labeled_text("Best: ", &config),
+                        BestText,
+                    ));
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                column_gap: LIFE_ICON_GAP,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        LifeIcons,
+                    ));
+                });
+        });
+}
+
+pub fn update_score_text(
+    scoreboard: Res<ScoreBoard>,
+    mut query: Query<(&mut Text, Ref<ScoreText>)>,
+) {
+    for (mut text, widget) in &mut query {
+        if scoreboard.is_changed() || widget.is_added() {
+            text.sections[1].value =
+                scoreboard.score.to_string();
+        }
+    }
+}
+
+pub fn update_level_text(
+    current_level: Option<Res<CurrentLevel>>,
+    mut query: Query<(&mut Text, Ref<LevelText>)>,
+) {
+    let Some(current_level) = current_level else {
+        return;
+    };
+    for (mut text, widget) in &mut query {
+        if current_level.is_changed() || widget.is_added() {
+            text.sections[1].value =
+                (current_level.0 + 1).to_string();
+        }
+    }
+}
+
+/// Shows the best score in the high score table, or the current one
+/// once it is higher.
+pub fn update_best_text(
+    scoreboard: Res<ScoreBoard>,
+    high_scores: Option<Res<HighScores>>,
+    mut query: Query<(&mut Text, Ref<BestText>)>,
+) {
+    let high_scores_changed = high_scores
+        .as_ref()
+        .is_some_and(|high_scores| high_scores.is_changed());
+    let best = high_scores
+        .as_deref()
+        .and_then(|high_scores| high_scores.entries.first())
+        .map_or(0, |entry| entry.score)
+        .max(scoreboard.score);
+
+    for (mut text, widget) in &mut query {
+        if scoreboard.is_changed()
+            || high_scores_changed
+            || widget.is_added()
+        {
+            text.sections[1].value = best.to_string();
+        }
+    }
+}
+
+pub fn update_combo_text(
+    scoreboard: Res<ScoreBoard>,
+    mut query: Query<(
+        &mut Visibility,
+        &mut Text,
+        Ref<ComboText>,
+    )>,
+) {
+    for (mut visibility, mut text, widget) in &mut query {
+        if !scoreboard.is_changed() && !widget.is_added() {
+            continue;
+        }
+        let combo = scoreboard.combo;
+        text.sections[1].value =
+            format!("{combo} x{:.2}", combo_multiplier(combo));
+        *visibility = if combo >= MIN_COMBO_SHOWN {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Shows one icon per life left.
+pub fn update_life_icons(
+    mut commands: Commands,
+    lives: Res<Lives>,
+    config: Res<GameConfig>,
+    query: Query<(Entity, Option<&Children>, Ref<LifeIcons>)>,
+) {
+    for (entity, children, widget) in &query {
+        if !lives.is_changed() && !widget.is_added() {
+            continue;
+        }
+        let icons =
+            children.map_or(&[][..], |children| &children[..]);
+        for &icon in icons.iter().skip(lives.remaining) {
+            commands.entity(icon).despawn_recursive();
+        }
+        commands.entity(entity).with_children(|parent| {
+            for _ in icons.len()..lives.remaining {
+                parent.spawn(life_icon(&config));
+            }
+        });
+    }
+}
+
+/// Pops up the points of every [`ScoreEvent`]. Bonuses that weren't
+/// scored anywhere in particular are stacked in the middle.
+pub fn spawn_score_popups(
+    mut commands: Commands,
+    mut score_events: EventReader<ScoreEvent>,
+    config: Res<GameConfig>,
+) {
+    let mut stacked = 0;
+    for event in score_events.iter() {
+        let position = event.position.unwrap_or_else(|| {
+            stacked += 1;
+            Vec2::new(
+                0.0,
+                -POPUP_STACK_GAP * (stacked - 1) as f32,
+            )
+        });
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("+{}", event.points),
+                    TextStyle {
+                        font_size: POPUP_FONT_SIZE,
+                        color: config.score_color,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(
+                    position.extend(POPUP_Z),
+                ),
+                ..default()
+            },
+            ScorePopup {
+                timer: Timer::from_seconds(
+                    POPUP_LIFETIME,
+                    TimerMode::Once,
+                ),
+            },
+            InGame,
+        ));
+    }
+}
+
+pub fn animate_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut ScorePopup,
+        &mut Transform,
+        &mut Text,
+    )>,
+) {
+    for (entity, mut popup, mut transform, mut text) in
+        &mut query
+    {
+        if popup.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y +=
+            POPUP_RISE_SPEED * time.delta_seconds();
+        let alpha = popup.timer.percent_left();
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+/// Recolors the HUD to match the config.
+#[allow(clippy::type_complexity)]
+pub fn update_hud_colors(
+    config: Res<GameConfig>,
+    mut text_query: Query<
+        &mut Text,
+        Or<(
+            With<ScoreText>,
+            With<LevelText>,
+            With<BestText>,
+            With<ComboText>,
+        )>,
+    >,
+    mut icon_query: Query<&mut BackgroundColor, With<LifeIcon>>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].style.color = config.text_color;
+        text.sections[1].style.color = config.score_color;
+    }
+    for mut background_color in &mut icon_query {
+        background_color.0 = config.ball_color;
+    }
+}
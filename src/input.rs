@@ -0,0 +1,324 @@
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{
+        GamepadConnection, GamepadConnectionEvent,
+        GamepadRumbleIntensity, GamepadRumbleRequest,
+    },
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::{
+    action::Action,
+    config::GameConfig,
+    game::{GameState, GameplaySet, LifeLostEvent},
+    physics::BrickDestroyedEvent,
+};
+
+pub const MOUSE_SMOOTHING: f32 = 0.03;
+pub const GRAB_CURSOR: bool = false;
+// A short buzz for every step that breaks bricks, and a heavy one
+// for losing a life
+pub const BRICK_RUMBLE_INTENSITY: f32 = 0.3;
+pub const BRICK_RUMBLE_DURATION: f32 = 0.08;
+pub const LIFE_LOST_RUMBLE_INTENSITY: f32 = 1.0;
+pub const LIFE_LOST_RUMBLE_DURATION: f32 = 0.4;
+
+/// Lets the player steer the paddle and launch caught balls with the
+/// keys bound in the config.
+pub struct KeyboardPlugin;
+
+impl Plugin for KeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, keyboard_paddle_intent);
+    }
+}
+
+/// Lets the player point the paddle at the cursor and launch caught
+/// balls with the left button. Needs a window and a 2D camera.
+pub struct MousePlugin;
+
+impl Plugin for MousePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, mouse_paddle_intent)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                grab_cursor,
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                release_cursor,
+            );
+    }
+}
+
+/// Lets the player steer the paddle with a gamepad's left stick or
+/// d-pad, launch caught balls with the south button and pause with
+/// start. The gamepad rumbles when bricks break and lives are lost.
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveGamepad>()
+            .add_systems(
+                Update,
+                (
+                    track_gamepads,
+                    // The keyboard stops the paddle whenever none of
+                    // its keys are held, so steer after it
+                    gamepad_paddle_intent
+                        .after(keyboard_paddle_intent),
+                    gamepad_pause,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                rumble_gamepad.after(GameplaySet),
+            );
+    }
+}
+
+/// The gamepad controlling the paddle, if any is connected.
+#[derive(Resource, Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// What the player wants the paddle to do, gathered from whichever
+/// device or bot is controlling it and consumed by the fixed step.
+#[derive(Resource, Default, Debug)]
+pub struct PaddleIntent {
+    /// Fraction of the paddle's speed to move at, from -1 (left) to 1
+    /// (right).
+    pub direction: f32,
+    /// Horizontal world position to move the paddle to instead, set
+    /// by pointing devices until another device takes over.
+    pub target: Option<f32>,
+    /// Set until the fixed step launches any balls held by the
+    /// paddle.
+    pub launch: bool,
+}
+
+pub fn keyboard_paddle_intent(
+    input: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    mut intent: ResMut<PaddleIntent>,
+) {
+    let bindings = &config.bindings;
+    let mut direction = 0.0;
+
+    if bindings.pressed(&input, Action::MoveLeft) {
+        direction -= 1.0;
+    }
+    if bindings.pressed(&input, Action::MoveRight) {
+        direction += 1.0;
+    }
+
+    intent.direction = direction;
+    if direction != 0.0 {
+        intent.target = None;
+    }
+    // A press can land between fixed steps, so hold on to it until
+    // one of them runs
+    intent.launch |=
+        bindings.just_pressed(&input, Action::Launch);
+}
+
+pub fn mouse_paddle_intent(
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    buttons: Res<Input<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut intent: ResMut<PaddleIntent>,
+) {
+    intent.launch |= buttons.just_pressed(MouseButton::Left);
+
+    // Only take over from other devices once the mouse is moved
+    if cursor_moved_events.iter().count() == 0 {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) =
+        camera_query.get_single()
+    else {
+        return;
+    };
+    if let Some(position) =
+        window.cursor_position().and_then(|cursor| {
+            camera.viewport_to_world_2d(camera_transform, cursor)
+        })
+    {
+        intent.target = Some(position.x);
+    }
+}
+
+/// Keeps the cursor inside the window and out of sight while playing,
+/// if the config asks for it.
+pub fn grab_cursor(
+    config: Res<GameConfig>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !config.grab_cursor {
+        return;
+    }
+    for mut window in &mut window_query {
+        window.cursor.grab_mode = CursorGrabMode::Confined;
+        window.cursor.visible = false;
+    }
+}
+
+pub fn release_cursor(
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    for mut window in &mut window_query {
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    }
+}
+
+/// Hands the paddle to the first gamepad connected, and to another
+/// one when it is disconnected.
+pub fn track_gamepads(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active_gamepad: ResMut<ActiveGamepad>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!(
+                    "Gamepad {} connected: {}",
+                    event.gamepad.id, info.name
+                );
+                if active_gamepad.0.is_none() {
+                    active_gamepad.0 = Some(event.gamepad);
+                }
+            }
+            GamepadConnection::Disconnected => {
+                info!(
+                    "Gamepad {} disconnected",
+                    event.gamepad.id
+                );
+                if active_gamepad.0 != Some(event.gamepad) {
+                    continue;
+                }
+                active_gamepad.0 = gamepads
+                    .iter()
+                    .find(|&gamepad| gamepad != event.gamepad);
+                // Give the player a chance to pick it back up
+                if *state.get() == GameState::Playing {
+                    next_state.set(GameState::Paused);
+                }
+            }
+        }
+    }
+}
+
+pub fn gamepad_paddle_intent(
+    active_gamepad: Res<ActiveGamepad>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut intent: ResMut<PaddleIntent>,
+    mut was_steering: Local<bool>,
+) {
+    let Some(gamepad) = active_gamepad.0 else {
+        return;
+    };
+
+    let mut direction = axes
+        .get(GamepadAxis::new(
+            gamepad,
+            GamepadAxisType::LeftStickX,
+        ))
+        .unwrap_or(0.0);
+    if buttons.pressed(GamepadButton::new(
+        gamepad,
+        GamepadButtonType::DPadLeft,
+    )) {
+        direction -= 1.0;
+    }
+    if buttons.pressed(GamepadButton::new(
+        gamepad,
+        GamepadButtonType::DPadRight,
+    )) {
+        direction += 1.0;
+    }
+
+    // Leave the paddle to the other devices unless the gamepad is
+    // steering it, or has only just let go
+    if direction != 0.0 {
+        intent.direction = direction.clamp(-1.0, 1.0);
+        intent.target = None;
+    } else if *was_steering {
+        intent.direction = 0.0;
+    }
+    *was_steering = direction != 0.0;
+
+    intent.launch |= buttons.just_pressed(GamepadButton::new(
+        gamepad,
+        GamepadButtonType::South,
+    ));
+}
+
+pub fn gamepad_pause(
+    active_gamepad: Res<ActiveGamepad>,
+    buttons: Res<Input<GamepadButton>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(gamepad) = active_gamepad.0 else {
+        return;
+    };
+    if !buttons.just_pressed(GamepadButton::new(
+        gamepad,
+        GamepadButtonType::Start,
+    )) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+pub fn rumble_gamepad(
+    active_gamepad: Res<ActiveGamepad>,
+    mut brick_destroyed_events: EventReader<BrickDestroyedEvent>,
+    mut life_lost_events: EventReader<LifeLostEvent>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+) {
+    let bricks_destroyed = brick_destroyed_events.iter().count();
+    let lives_lost = life_lost_events.iter().count();
+    let Some(gamepad) = active_gamepad.0 else {
+        return;
+    };
+
+    if bricks_destroyed > 0 {
+        rumble_requests.send(GamepadRumbleRequest::Add {
+            gamepad,
+            intensity: GamepadRumbleIntensity::weak_motor(
+                BRICK_RUMBLE_INTENSITY,
+            ),
+            duration: Duration::from_secs_f32(
+                BRICK_RUMBLE_DURATION,
+            ),
+        });
+    }
+    for _ in 0..lives_lost {
+        rumble_requests.send(GamepadRumbleRequest::Add {
+            gamepad,
+            intensity: GamepadRumbleIntensity::strong_motor(
+                LIFE_LOST_RUMBLE_INTENSITY,
+            ),
+            duration: Duration::from_secs_f32(
+                LIFE_LOST_RUMBLE_DURATION,
+            ),
+        });
+    }
+}
@@ -0,0 +1,727 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    components::{
+        ball::BallSpeed,
+        brick::{Brick, BrickType},
+        paddle::Paddle,
+    },
+    config::GameConfig,
+    game::InGame,
+    physics::Collider,
+};
+
+/// Every file in this folder with a level extension is part of the
+/// game, played in file name order.
+pub const LEVELS_FOLDER: &str = "levels";
+
+/// Grid cells holding this character (or a space) have no brick.
+pub const EMPTY_CELL: char = '.';
+/// Lines of an ASCII level starting with this character are ignored.
+pub const COMMENT_PREFIX: char = ';';
+
+/// Symbols usable in every level without declaring them in a legend,
+/// as (symbol, color, hits, points). Zero hits makes a steel brick.
+pub const DEFAULT_LEGEND: [(char, &str, u32, usize); 11] = [
+    ('R', "#e74c3c", 1, 1),
+    ('O', "#e67e22", 1, 1),
+    ('Y', "#f1c40f", 1, 1),
+    ('G', "#2ecc71", 1, 1),
+    ('C', "#1abc9c", 1, 1),
+    ('B', "#3498db", 1, 1),
+    ('P', "#9b59b6", 1, 1),
+    ('W', "#ecf0f1", 1, 1),
+    ('2', "#bdc3c7", 2, 3),
+    ('3', "#d4ac0d", 3, 5),
+    ('#', "#566573", 0, 0),
+];
+
+/// Loads the level files from `assets/levels`.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<StartingLevel>()
+            .add_systems(Startup, load_levels)
+            .add_systems(
+                Update,
+                (
+                    skip_failed_levels,
+                    update_brick_layout
+                        .run_if(resource_changed::<GameConfig>()),
+                    // Only a game in progress has bricks to replace
+                    reload_changed_level
+                        .run_if(any_with_component::<Paddle>()),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("level file is not valid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("could not parse level file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("level has no bricks")]
+    Empty,
+    #[error(
+        "unknown brick '{symbol}' at row {row}, column {column}"
+    )]
+    UnknownBrick {
+        symbol: char,
+        row: usize,
+        column: usize,
+    },
+    #[error("brick '{symbol}' has an invalid color '{color}'")]
+    InvalidColor { symbol: char, color: String },
+    #[error("brick '{symbol}' needs at least one hit to break")]
+    NoHits { symbol: char },
+    #[error("level has only indestructible bricks")]
+    Unbeatable,
+    #[error(
+        "level ({columns}x{rows}) does not fit in the arena"
+    )]
+    TooLarge { columns: usize, rows: usize },
+}
+
+/// The cell of the level's grid a brick was spawned in.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BrickCell {
+    pub column: usize,
+    pub row: usize,
+}
+
+/// A single brick in a level, addressed by its cell in the grid.
+/// Row 0 is the top row.
+#[derive(Debug, Clone)]
+pub struct LevelBrick {
+    pub column: usize,
+    pub row: usize,
+    pub brick_type: BrickType,
+}
+
+#[derive(Debug, TypeUuid, TypePath)]
+#[uuid = "f054b536-d4b6-4277-8e2d-0bb8d3fb86e0"]
+pub struct Level {
+    pub name: String,
+    pub columns: usize,
+    pub rows: usize,
+    pub bricks: Vec<LevelBrick>,
+    /// Overrides the speed the ball would otherwise get from the
+    /// level's position in the campaign.
+    pub ball_speed: Option<f32>,
+}
+
+/// A brick type as written in a level file.
+#[derive(Debug, Deserialize)]
+struct BrickTypeFile {
+    color: String,
+    #[serde(default = "default_hits")]
+    hits: u32,
+    #[serde(default)]
+    indestructible: bool,
+    /// Defaults to one point per hit needed to break the brick.
+    #[serde(default)]
+    points: Option<usize>,
+}
+
+fn default_hits() -> u32 {
+    1
+}
+
+/// The RON variant of a level: a grid of symbols plus a legend that
+/// adds to (or overrides) [`DEFAULT_LEGEND`].
+#[derive(Debug, Deserialize)]
+struct LevelFile {
+    name: String,
+    #[serde(default)]
+    legend: HashMap<char, BrickTypeFile>,
+    grid: Vec<String>,
+    #[serde(default)]
+    ball_speed: Option<f32>,
+}
+
+impl Level {
+    /// Parses a plain grid of symbols using [`DEFAULT_LEGEND`].
+    pub fn from_ascii(
+        name: &str,
+        text: &str,
+    ) -> Result<Level, LevelError> {
+        let grid: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.starts_with(COMMENT_PREFIX))
+            .collect();
+
+        Level::from_grid(
+            name.to_string(),
+            &grid,
+            &HashMap::default(),
+        )
+    }
+
+    pub fn from_ron(text: &str) -> Result<Level, LevelError> {
+        let file: LevelFile = ron::from_str(text)?;
+        let grid: Vec<&str> =
+            file.grid.iter().map(String::as_str).collect();
+
+        let mut level =
+            Level::from_grid(file.name, &grid, &file.legend)?;
+        level.ball_speed = file.ball_speed;
+        Ok(level)
+    }
+
+    /// Speed of the ball when this level is played as the level at
+    /// `index` of the campaign.
+    pub fn ball_speed(
+        &self,
+        index: usize,
+        config: &GameConfig,
+    ) -> f32 {
+        self.ball_speed
+            .unwrap_or_else(|| config.ball_speed(index))
+    }
+
+    /// Center of the brick in `cell`, with the level centered
+    /// horizontally and hanging from the ceiling.
+    pub fn brick_position(
+        &self,
+        cell: BrickCell,
+        config: &GameConfig,
+    ) -> Vec2 {
+        let brick_size = config.brick_size;
+        let gap = config.gap_between_bricks;
+        let total_width_of_bricks =
+            self.columns as f32 * (brick_size.x + gap) - gap;
+
+        // In Bevy, the `translation` of an entity describes the center
+        // point, not its top-left corner
+        let center_of_bricks =
+            (config.left_wall() + config.right_wall()) / 2.0;
+        let offset_x = center_of_bricks
+            - total_width_of_bricks / 2.0
+            + brick_size.x / 2.0;
+        let offset_y = config.top_wall()
+            - config.gap_between_bricks_and_ceiling
+            - brick_size.y / 2.0;
+
+        Vec2::new(
+            offset_x + cell.column as f32 * (brick_size.x + gap),
+            offset_y - cell.row as f32 * (brick_size.y + gap),
+        )
+    }
+
+    /// Checks that the bricks fit between the side walls and above
+    /// the paddle with the sizes and gaps in `config`.
+    pub fn fits(
+        &self,
+        config: &GameConfig,
+    ) -> Result<(), LevelError> {
+        let brick_size = config.brick_size;
+        let gap = config.gap_between_bricks;
+        let total_width_of_bricks =
+            self.columns as f32 * (brick_size.x + gap) - gap;
+        let total_height_of_bricks =
+            self.rows as f32 * (brick_size.y + gap) - gap;
+
+        let available_width = (config.right_wall()
+            - config.left_wall())
+            - 2. * config.gap_between_bricks_and_sides;
+        let available_height = config.top_wall()
+            - config.gap_between_bricks_and_ceiling
+            - (config.paddle_y()
+                + config.gap_between_paddle_and_bricks);
+        if total_width_of_bricks > available_width
+            || total_height_of_bricks > available_height
+        {
+            return Err(LevelError::TooLarge {
+                columns: self.columns,
+                rows: self.rows,
+            });
+        }
+        Ok(())
+    }
+
+    fn from_grid(
+        name: String,
+        grid: &[&str],
+        legend: &HashMap<char, BrickTypeFile>,
+    ) -> Result<Level, LevelError> {
+        let mut brick_types = HashMap::default();
+        for (symbol, color, hits, points) in DEFAULT_LEGEND {
+            let color = Color::hex(color).unwrap();
+            let brick_type = if hits == 0 {
+                BrickType::indestructible(symbol, color)
+            } else {
+                BrickType::breakable(symbol, color, hits, points)
+            };
+            brick_types.insert(symbol, brick_type);
+        }
+        for (&symbol, file) in legend {
+            let color =
+                Color::hex(&file.color).map_err(|_| {
+                    LevelError::InvalidColor {
+                        symbol,
+                        color: file.color.clone(),
+                    }
+                })?;
+            let brick_type = if file.indestructible {
+                BrickType::indestructible(symbol, color)
+            } else if file.hits == 0 {
+                return Err(LevelError::NoHits { symbol });
+            } else {
+                BrickType::breakable(
+                    symbol,
+                    color,
+                    file.hits,
+                    file.points.unwrap_or(file.hits as usize),
+                )
+            };
+            brick_types.insert(symbol, brick_type);
+        }
+
+        // Trailing blank lines would otherwise count as empty rows
+        let rows = grid
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map_or(0, |last| last + 1);
+
+        let mut columns = 0;
+        let mut bricks = Vec::new();
+        for (row, line) in grid[..rows].iter().enumerate() {
+            for (column, symbol) in
+                line.trim_end().chars().enumerate()
+            {
+                if symbol == EMPTY_CELL || symbol == ' ' {
+                    continue;
+                }
+                let Some(&brick_type) = brick_types.get(&symbol)
+                else {
+                    return Err(LevelError::UnknownBrick {
+                        symbol,
+                        row,
+                        column,
+                    });
+                };
+                columns = columns.max(column + 1);
+                bricks.push(LevelBrick {
+                    column,
+                    row,
+                    brick_type,
+                });
+            }
+        }
+
+        if bricks.is_empty() {
+            return Err(LevelError::Empty);
+        }
+        if !bricks
+            .iter()
+            .any(|brick| brick.brick_type.is_breakable())
+        {
+            return Err(LevelError::Unbeatable);
+        }
+
+        Ok(Level {
+            name,
+            columns,
+            rows,
+            bricks,
+            ball_speed: None,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)
+                .map_err(LevelError::from)?;
+            let path = load_context.path();
+            let level = if path
+                .extension()
+                .is_some_and(|extension| extension == "ron")
+            {
+                Level::from_ron(text)?
+            } else {
+                let name = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default();
+                Level::from_ascii(name, text)?
+            };
+            load_context
+                .set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level", "level.ron"]
+    }
+}
+
+/// All levels of the game, in the order they are played.
+#[derive(Resource, Default)]
+pub struct Levels(pub Vec<Handle<Level>>);
+
+impl Levels {
+    pub fn is_last(&self, index: usize) -> bool {
+        index + 1 >= self.0.len()
+    }
+
+    pub fn all_loaded(
+        &self,
+        level_assets: &Assets<Level>,
+    ) -> bool {
+        !self.0.is_empty()
+            && self
+                .0
+                .iter()
+                .all(|handle| level_assets.contains(handle))
+    }
+}
+
+/// Index into [`Levels`] of the level a new game starts on.
+#[derive(Resource, Default)]
+pub struct StartingLevel(pub usize);
+
+/// Index into [`Levels`] of the level being played.
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize);
+
+pub fn load_levels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let mut handles: Vec<Handle<Level>> =
+        match asset_server.load_folder(LEVELS_FOLDER) {
+            Ok(handles) => handles
+                .into_iter()
+                .map(|handle| handle.typed())
+                .collect(),
+            Err(err) => {
+                error!("Could not load levels: {err}");
+                Vec::new()
+            }
+        };
+    handles.sort_by_key(|handle| {
+        asset_server
+            .get_handle_path(handle)
+            .map(|path| path.path().to_path_buf())
+    });
+
+    commands.insert_resource(Levels(handles));
+}
+
+/// Drops the levels whose files failed to load, so that one broken
+/// file doesn't keep the rest from being played. The asset server
+/// has already logged why each one failed.
+pub fn skip_failed_levels(
+    mut levels: ResMut<Levels>,
+    level_assets: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+) {
+    // A level that loaded once stays playable even if reloading it
+    // fails, and its index may be the one being played
+    let failed = |handle: &Handle<Level>| {
+        !level_assets.contains(handle)
+            && asset_server.get_load_state(handle)
+                == LoadState::Failed
+    };
+    if !levels.0.iter().any(failed) {
+        return;
+    }
+    levels.0.retain(|handle| {
+        if !failed(handle) {
+            return true;
+        }
+        warn!(
+            "Skipping level {:?}, which failed to load",
+            asset_server
+                .get_handle_path(handle)
+                .map(|path| path.path().to_path_buf())
+        );
+        false
+    });
+}
+
+/// Spawns the bricks of `level`, centered horizontally and hanging
+/// from the ceiling.
+pub fn spawn_level(
+    commands: &mut Commands,
+    level: &Level,
+    config: &GameConfig,
+) {
+    if let Err(err) = level.fits(config) {
+        warn!("Level '{}': {err}", level.name);
+    }
+
+    for brick in &level.bricks {
+        let cell = BrickCell {
+            column: brick.column,
+            row: brick.row,
+        };
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: brick.brick_type.color,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: level
+                        .brick_position(cell, config)
+                        .extend(0.0),
+                    scale: config.brick_size.extend(1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Brick(brick.brick_type),
+            cell,
+            Collider,
+            InGame,
+        ));
+    }
+}
+
+/// Lays the bricks of the current level out again to match the
+/// config, and updates the ball speed it gives.
+pub fn update_brick_layout(
+    config: Res<GameConfig>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    current_level: Option<Res<CurrentLevel>>,
+    ball_speed: Option<ResMut<BallSpeed>>,
+    mut brick_query: Query<(&BrickCell, &mut Transform)>,
+) {
+    let Some(current_level) = current_level else {
+        return;
+    };
+    let Some(level) =
+        level_assets.get(&levels.0[current_level.0])
+    else {
+        return;
+    };
+
+    for (cell, mut transform) in &mut brick_query {
+        transform.translation =
+            level.brick_position(*cell, &config).extend(0.0);
+        transform.scale = config.brick_size.extend(1.0);
+    }
+    if let Some(mut ball_speed) = ball_speed {
+        ball_speed.0 =
+            level.ball_speed(current_level.0, &config);
+    }
+}
+
+/// Replaces the bricks of the level being played when its file
+/// changes. Bricks already broken come back, as the layout may no
+/// longer match the old one.
+pub fn reload_changed_level(
+    mut commands: Commands,
+    mut level_events: EventReader<AssetEvent<Level>>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    current_level: Res<CurrentLevel>,
+    config: Res<GameConfig>,
+    brick_query: Query<Entity, With<Brick>>,
+) {
+    let current_handle = &levels.0[current_level.0];
+    let modified = level_events.iter().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Modified { handle } if handle == current_handle
+        )
+    });
+    let Some(level) = level_assets.get(current_handle) else {
+        return;
+    };
+    if !modified {
+        return;
+    }
+
+    info!(
+        "Reloading level {}: {}",
+        current_level.0 + 1,
+        level.name
+    );
+    for entity in &brick_query {
+        commands.entity(entity).despawn();
+    }
+    spawn_level(&mut commands, level, &config);
+    commands.insert_resource(BallSpeed(
+        level.ball_speed(current_level.0, &config),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_symbol_is_reported_with_its_cell() {
+        let error =
+            Level::from_ascii("test", "RRR\nR?R").unwrap_err();
+        assert!(matches!(
+            error,
+            LevelError::UnknownBrick {
+                symbol: '?',
+                row: 1,
+                column: 1,
+            }
+        ));
+    }
+
+    #[test]
+    fn legend_adds_symbols_to_the_default_ones() {
+        let level = Level::from_ron(
+            r##"(
+                name: "legend",
+                legend: { 'x': (color: "#ff0000", hits: 2) },
+                grid: ["RxR"],
+            )"##,
+        )
+        .unwrap();
+        assert_eq!(level.bricks.len(), 3);
+        assert_eq!(level.bricks[1].brick_type.points, 2);
+    }
+
+    #[test]
+    fn symbol_missing_from_the_legend_is_unknown() {
+        let error = Level::from_ron(
+            r##"(
+                name: "legend",
+                legend: { 'x': (color: "#ff0000") },
+                grid: ["xy"],
+            )"##,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LevelError::UnknownBrick { symbol: 'y', .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_legend_entries_are_rejected() {
+        let error = Level::from_ron(
+            r#"(
+                name: "color",
+                legend: { 'x': (color: "red") },
+                grid: ["x"],
+            )"#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LevelError::InvalidColor { symbol: 'x', .. }
+        ));
+
+        let error = Level::from_ron(
+            r##"(
+                name: "hits",
+                legend: { 'x': (color: "#ff0000", hits: 0) },
+                grid: ["x"],
+            )"##,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LevelError::NoHits { symbol: 'x' }
+        ));
+    }
+
+    #[test]
+    fn ragged_rows_are_as_wide_as_the_widest() {
+        let level = Level::from_ascii(
+            "ragged",
+            "; a comment\nRR\nRRRR\nR\n\n\n",
+        )
+        .unwrap();
+        assert_eq!(level.columns, 4);
+        assert_eq!(level.rows, 3);
+        assert_eq!(level.bricks.len(), 7);
+        assert!(level
+            .bricks
+            .iter()
+            .any(|brick| brick.column == 3 && brick.row == 1));
+    }
+
+    #[test]
+    fn levels_without_breakable_bricks_are_rejected() {
+        assert!(matches!(
+            Level::from_ascii("empty", ". .\n\n"),
+            Err(LevelError::Empty)
+        ));
+        assert!(matches!(
+            Level::from_ascii("steel", "##"),
+            Err(LevelError::Unbeatable)
+        ));
+    }
+
+    #[test]
+    fn grid_too_large_for_the_arena_does_not_fit() {
+        let config = GameConfig::default();
+        let wide = "R".repeat(100);
+        let level = Level::from_ascii("wide", &wide).unwrap();
+        assert!(matches!(
+            level.fits(&config),
+            Err(LevelError::TooLarge {
+                columns: 100,
+                rows: 1,
+            })
+        ));
+
+        let tall = "R\n".repeat(100);
+        let level = Level::from_ascii("tall", &tall).unwrap();
+        assert!(matches!(
+            level.fits(&config),
+            Err(LevelError::TooLarge {
+                columns: 1,
+                rows: 100,
+            })
+        ));
+    }
+
+    #[test]
+    fn shipped_levels_load_and_fit() {
+        let config = GameConfig::default();
+        let folder =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join(LEVELS_FOLDER);
+        for entry in std::fs::read_dir(folder).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            let name =
+                path.file_name().unwrap().to_string_lossy();
+            let level = if name.ends_with(".level.ron") {
+                Level::from_ron(&text)
+            } else {
+                Level::from_ascii(&name, &text)
+            }
+            .unwrap_or_else(|err| panic!("{name}: {err}"));
+            level
+                .fits(&config)
+                .unwrap_or_else(|err| panic!("{name}: {err}"));
+        }
+    }
+}
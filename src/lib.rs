@@ -0,0 +1,69 @@
+//! Breakout, built as a set of Bevy plugins.
+//!
+//! Add [`BreakoutPlugin`] to an app with a window to play the whole
+//! game. It is a plugin group, so parts of it can be left out, for
+//! example to run the game in a test without audio or a keyboard:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use breakout::{audio::AudioPlugin, input::KeyboardPlugin, BreakoutPlugin};
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(
+//!         BreakoutPlugin
+//!             .build()
+//!             .disable::<AudioPlugin>()
+//!             .disable::<KeyboardPlugin>(),
+//!     )
+//!     .run();
+//! ```
+//!
+//! Every tuning value is read from the [`config::GameConfig`]
+//! resource, which [`config::ConfigPlugin`] reads from
+//! `assets/breakout.config.ron`, or the file named by a
+//! [`config::ConfigSource`], and reads again whenever the file
+//! changes if the app's `AssetPlugin` watches for changes.
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod action;
+pub mod audio;
+pub mod components;
+pub mod config;
+pub mod game;
+pub mod headless;
+pub mod highscore;
+pub mod hud;
+pub mod input;
+pub mod level;
+pub mod menu;
+pub mod physics;
+pub mod powerup;
+pub mod replay;
+pub mod score;
+pub mod ui;
+
+/// Every plugin that makes up the game. The app is expected to
+/// provide the camera.
+pub struct BreakoutPlugin;
+
+impl PluginGroup for BreakoutPlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(config::ConfigPlugin)
+            .add(game::GamePlugin)
+            .add(level::LevelPlugin)
+            .add(physics::PhysicsPlugin)
+            .add(powerup::PowerUpPlugin)
+            .add(score::ScorePlugin)
+            .add(ui::UiPlugin)
+            .add(hud::HudPlugin)
+            .add(menu::MenuPlugin)
+            .add(highscore::HighScorePlugin)
+            .add(audio::AudioPlugin)
+            .add(input::KeyboardPlugin)
+            .add(input::MousePlugin)
+            .add(input::GamepadPlugin)
+    }
+}
@@ -1,221 +1,192 @@
+use std::time::Duration;
+
 use bevy::{
-    app::PluginGroupBuilder, prelude::*,
-    sprite::MaterialMesh2dBundle,
-};
-use components::{
-    ball::*, paddle::*, wall::*, SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::PluginGroupBuilder, asset::ChangeWatcher, prelude::*,
+    time::TimeUpdateStrategy,
 };
-use physics::*;
-use ui::*;
-
-use crate::components::brick::{
-    Brick, BRICK_SIZE, GAP_BETWEEN_BRICKS,
-    GAP_BETWEEN_BRICKS_AND_CEILING,
-    GAP_BETWEEN_BRICKS_AND_SIDES, GAP_BETWEEN_PADDLE_AND_BRICKS,
+use breakout::{
+    audio::AudioPlugin,
+    config::{ConfigSource, GameConfig, DEFAULT_CONFIG_PATH},
+    game::*,
+    headless::*,
+    highscore::HighScorePlugin,
+    input::{GamepadPlugin, KeyboardPlugin, MousePlugin},
+    level::StartingLevel,
+    menu::skip_main_menu,
+    physics::TIME_STEP,
+    replay::*,
+    BreakoutPlugin,
 };
+use cli::Args;
 
-mod components;
-mod physics;
-mod ui;
+mod cli;
 
 fn main() {
-    App::new()
-        .add_plugins(custom_plugins())
-        .insert_resource(ScoreBoard { score: 0 })
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .add_event::<CollisionEvent>()
-        // Configure how frequently our gameplay systems are run
-        .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
-        .add_systems(Startup, setup)
-        // Add our gameplay simulation systems to the fixed timestep schedule
-        .add_systems(
-            FixedUpdate,
-            (
-                check_for_collisions,
-                apply_velocity.before(check_for_collisions),
-                move_paddle
-                    .before(check_for_collisions)
-                    .after(apply_velocity),
-                play_collision_sound.after(check_for_collisions),
-            ),
-        )
-        .add_systems(
-            Update,
-            (update_scoreboard, bevy::window::close_on_esc),
-        )
-        .run();
-}
-
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
-) {
-    // Camera
-    commands.spawn(Camera2dBundle::default());
-
-    // Sound
-    let ball_collision_sound =
-        asset_server.load("sounds/breakout_collision.ogg");
-    commands
-        .insert_resource(CollisionSound(ball_collision_sound));
-
-    // Paddle
-    let paddle_y = BOTTOM_WALL + GAP_BETWEEN_PADDLE_AND_FLOOR;
-
-    commands.spawn((
-        SpriteBundle {
-            transform: Transform {
-                translation: Vec3::new(0.0, paddle_y, 0.0),
-                scale: PADDLE_SIZE,
-                ..default()
-            },
-            sprite: Sprite {
-                color: PADDLE_COLOR,
-                ..default()
-            },
-            ..default()
-        },
-        Paddle,
-        Collider,
-    ));
-
-    // Ball
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: meshes
-                .add(shape::Circle::default().into())
-                .into(),
-            material: materials
-                .add(ColorMaterial::from(BALL_COLOR)),
-            transform: Transform::from_translation(
-                BALL_STARTING_POSITION,
-            )
-            .with_scale(BALL_SIZE),
-            ..default()
+    let args = Args::from_env();
+    let config_source = ConfigSource {
+        // The asset server looks for relative paths in the assets
+        // folder rather than the working directory
+        path: match &args.config {
+            Some(path) => std::fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone()),
+            None => DEFAULT_CONFIG_PATH.into(),
         },
-        Ball,
-        Velocity(
-            INITIAL_BALL_DIRECTION.normalize() * BALL_SPEED,
-        ),
-    ));
-
-    // Scoreboard
-    commands.spawn(
-        TextBundle::from_sections([
-            TextSection::new(
-                "Score: ",
-                TextStyle {
-                    font_size: SCOREBOARD_FONT_SIZE,
-                    color: TEXT_COLOR,
-                    ..Default::default()
-                },
-            ),
-            TextSection::from_style(TextStyle {
-                font_size: SCOREBOARD_FONT_SIZE,
-                color: SCORE_COLOR,
-                ..Default::default()
-            }),
-        ])
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: SCOREBOARD_TEXT_PADDING,
-            left: SCOREBOARD_TEXT_PADDING,
-            ..Default::default()
-        }),
-    );
-
-    // Wall
-    commands.spawn(WallBundle::new(WallLocation::Top));
-    commands.spawn(WallBundle::new(WallLocation::Bottom));
-    commands.spawn(WallBundle::new(WallLocation::Left));
-    commands.spawn(WallBundle::new(WallLocation::Right));
-
-    // Bricks
-    assert!(BRICK_SIZE.x > 0.0);
-    assert!(BRICK_SIZE.y > 0.0);
-
-    let total_width_of_bricks = (RIGHT_WALL - LEFT_WALL)
-        - 2. * GAP_BETWEEN_BRICKS_AND_SIDES;
-    let bottom_edge_of_bricks =
-        paddle_y + GAP_BETWEEN_PADDLE_AND_BRICKS;
-    let total_height_of_bricks = TOP_WALL
-        - bottom_edge_of_bricks
-        - GAP_BETWEEN_BRICKS_AND_CEILING;
-
-    assert!(total_width_of_bricks > 0.0);
-    assert!(total_height_of_bricks > 0.0);
-
-    // Given the space available, compute how many rows and columns of bricks we can fit
-    let n_columns = (total_width_of_bricks
-        / (BRICK_SIZE.x + GAP_BETWEEN_BRICKS))
-        .floor() as usize;
-    let n_rows = (total_height_of_bricks
-        / (BRICK_SIZE.y + GAP_BETWEEN_BRICKS))
-        .floor() as usize;
-    let n_vertical_gaps = n_columns - 1;
+        user_settings: ConfigSource::default_user_settings(),
+        overrides: args.settings.clone(),
+    };
+    let (config, warning) =
+        GameConfig::load(args.config.as_deref(), &config_source)
+            .unwrap_or_else(|error| {
+                eprintln!("error: {error}");
+                std::process::exit(2);
+            });
+    if let Some(warning) = warning {
+        eprintln!("warning: {warning}");
+    }
+    let replay = args.replay.as_deref().map(|path| {
+        let replay =
+            Replay::load(path).unwrap_or_else(|error| {
+                eprintln!("error: {error}");
+                std::process::exit(1);
+            });
+        if replay.config_hash.is_none() {
+            eprintln!(
+                "warning: the replay doesn't say which settings it \
+                 was recorded with, so it only matches if they are \
+                 the same"
+            );
+        }
+        replay
+    });
+    let mut app = App::new();
+    app.insert_resource(config.clone())
+        .insert_resource(config_source);
+
+    let mut breakout = BreakoutPlugin.build();
+    // The paddle is driven by the bot or the replay instead, and
+    // their scores don't belong in the high score table
+    if args.headless || replay.is_some() {
+        breakout = breakout
+            .disable::<KeyboardPlugin>()
+            .disable::<MousePlugin>()
+            .disable::<GamepadPlugin>()
+            .disable::<HighScorePlugin>();
+    }
 
-    // Because we need to round the number of columns,
-    // the space on the top and sides of the bricks only captures a lower bound, not an exact value
-    let center_of_bricks = (LEFT_WALL + RIGHT_WALL) / 2.0;
-    let left_edge_of_bricks = center_of_bricks
-        // Space taken up by the bricks
-        - (n_columns as f32 / 2.0 * BRICK_SIZE.x)
-        // Space taken up by the gaps
-        - n_vertical_gaps as f32 / 2.0 * GAP_BETWEEN_BRICKS;
+    if args.headless {
+        // Every update advances time by exactly one fixed step, so
+        // the simulation runs as fast as the machine allows
+        app.add_plugins(headless_plugins())
+            .add_asset::<Mesh>()
+            .add_asset::<ColorMaterial>()
+            .add_plugins(breakout.disable::<AudioPlugin>())
+            .insert_resource(TimeUpdateStrategy::ManualDuration(
+                Duration::from_secs_f32(TIME_STEP),
+            ))
+            .insert_resource(HeadlessRun::new(
+                args.max_steps.unwrap_or(DEFAULT_MAX_STEPS),
+            ))
+            .add_systems(FixedUpdate, count_headless_steps)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                finish_headless_run,
+            );
+        if replay.is_none() {
+            app.add_systems(
+                FixedUpdate,
+                bot_paddle_intent.in_set(GameplayStage::Input),
+            );
+        }
+    } else {
+        app.add_plugins(custom_plugins(&config))
+            .add_plugins(breakout)
+            .add_systems(Startup, setup_camera);
+    }
 
-    // In Bevy, the `translation` of an entity describes the center point,
-    // not its bottom-left corner
-    let offset_x = left_edge_of_bricks + BRICK_SIZE.x / 2.;
-    let offset_y = bottom_edge_of_bricks + BRICK_SIZE.y / 2.;
+    // Nobody is at the menu when the game plays itself
+    if args.headless || replay.is_some() {
+        app.add_systems(
+            Update,
+            skip_main_menu.run_if(in_state(GameState::MainMenu)),
+        );
+    }
 
-    for row in 0..n_rows {
-        for column in 0..n_columns {
-            let brick_position = Vec2::new(
-                offset_x
-                    + column as f32
-                        * (BRICK_SIZE.x + GAP_BETWEEN_BRICKS),
-                offset_y
-                    + row as f32
-                        * (BRICK_SIZE.y + GAP_BETWEEN_BRICKS),
+    if let Some(replay) = replay {
+        app.insert_resource(GameSeed(Some(replay.seed)))
+            .insert_resource(StartingLevel(replay.level))
+            .insert_resource(ReplayPlayer::new(replay))
+            // The config file is only applied once the app runs, and
+            // always before the game starts
+            .add_systems(
+                OnEnter(GameState::Playing),
+                check_replay_config,
+            )
+            .add_systems(
+                FixedUpdate,
+                play_replay.in_set(GameplayStage::Input),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                finish_replay,
             );
+    } else {
+        app.insert_resource(GameSeed(args.seed))
+            .insert_resource(StartingLevel(args.level));
+    }
 
-            // brick
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: BRICK_COLOR,
-                        ..default()
-                    },
-                    transform: Transform {
-                        translation: brick_position.extend(0.0),
-                        scale: Vec3::new(
-                            BRICK_SIZE.x,
-                            BRICK_SIZE.y,
-                            1.0,
-                        ),
-                        ..default()
-                    },
-                    ..default()
-                },
-                Brick,
-                Collider,
-            ));
-        }
+    if let Some(path) = args.record {
+        app.insert_resource(ReplayRecorder::new(path))
+            .add_systems(
+                FixedUpdate,
+                record_input.in_set(GameplayStage::Record),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                finish_recording,
+            )
+            // Quitting from the pause screen also ends the game
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                finish_recording
+                    .before(despawn_screen::<InGame>),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                stop_recording
+                    .before(restart_level)
+                    .run_if(on_event::<RestartLevelEvent>()),
+            );
     }
+
+    app.run();
 }
 
-fn custom_plugins() -> PluginGroupBuilder {
-    DefaultPlugins.set(ImagePlugin::default_nearest()).set(
-        WindowPlugin {
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn custom_plugins(config: &GameConfig) -> PluginGroupBuilder {
+    DefaultPlugins
+        .set(ImagePlugin::default_nearest())
+        // Pick up changes to the config and level files while the
+        // game runs
+        .set(AssetPlugin {
+            watch_for_changes: ChangeWatcher::with_delay(
+                Duration::from_millis(200),
+            ),
+            ..default()
+        })
+        .set(WindowPlugin {
             primary_window: Some(Window {
-                resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
+                resolution: (
+                    config.screen_width,
+                    config.screen_height,
+                )
+                    .into(),
                 title: "App".into(),
                 resizable: false,
                 ..Default::default()
             }),
             ..Default::default()
-        },
-    )
+        })
 }
@@ -0,0 +1,845 @@
+use bevy::{app::AppExit, prelude::*, window::WindowFocused};
+
+use crate::{
+    action::Action,
+    config::{
+        save_setting, ConfigHandle, ConfigSource, GameConfig,
+    },
+    game::{
+        despawn_screen, GameState, Lives, RestartLevelEvent,
+    },
+    highscore::{
+        start_initials_entry, type_initials, HighScores,
+        InitialsEntry, INITIALS_LENGTH, SAVE_INITIALS_KEY,
+    },
+    level::{CurrentLevel, Level, Levels},
+    ui::ScoreBoard,
+};
+
+pub const MENU_TITLE_FONT_SIZE: f32 = 80.0;
+pub const MENU_TEXT_FONT_SIZE: f32 = 30.0;
+pub const MENU_ROW_GAP: Val = Val::Px(10.0);
+pub const OVERLAY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+pub const BUTTON_WIDTH: Val = Val::Px(360.0);
+pub const BUTTON_PADDING: UiRect = UiRect::all(Val::Px(10.0));
+pub const BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.1);
+pub const SELECTED_BUTTON_COLOR: Color =
+    Color::rgba(1.0, 1.0, 1.0, 0.3);
+// Seconds the level cleared screen waits before moving on by itself
+pub const LEVEL_CLEARED_DELAY: f32 = 3.0;
+// Keys for moving around the menus, which can't be rebound so that
+// the player can't lock themselves out of them
+pub const CONTROLS_KEY: KeyCode = KeyCode::F1;
+pub const HIGH_SCORES_KEY: KeyCode = KeyCode::F2;
+pub const MENU_PREVIOUS_KEY: KeyCode = KeyCode::Up;
+pub const MENU_NEXT_KEY: KeyCode = KeyCode::Down;
+pub const MENU_SELECT_KEY: KeyCode = KeyCode::Return;
+pub const CONTROLS_RESET_KEY: KeyCode = KeyCode::Back;
+pub const BACK_KEY: KeyCode = KeyCode::Escape;
+
+/// The screens shown around the game, and the actions that move
+/// between them.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                setup_main_menu,
+            )
+            .add_systems(
+                OnExit(GameState::MainMenu),
+                despawn_screen::<MainMenuScreen>,
+            )
+            .add_systems(
+                OnEnter(GameState::Paused),
+                setup_pause_screen,
+            )
+            .add_systems(
+                OnExit(GameState::Paused),
+                despawn_screen::<PauseScreen>,
+            )
+            .add_systems(
+                OnExit(GameState::Controls),
+                despawn_screen::<ControlsScreen>,
+            )
+            .add_systems(
+                OnEnter(GameState::HighScores),
+                setup_high_score_screen,
+            )
+            .add_systems(
+                OnExit(GameState::HighScores),
+                despawn_screen::<HighScoreScreen>,
+            )
+            .add_systems(
+                OnEnter(GameState::LevelCleared),
+                setup_level_cleared_screen,
+            )
+            .add_systems(
+                OnExit(GameState::LevelCleared),
+                despawn_screen::<LevelClearedScreen>,
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                setup_game_over_screen
+                    .after(start_initials_entry),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                despawn_screen::<GameOverScreen>,
+            )
+            .add_systems(
+                Update,
+                (
+                    main_menu_input
+                        .run_if(in_state(GameState::MainMenu)),
+                    (
+                        playing_input,
+                        // Without a window, focus never changes
+                        pause_on_focus_lost.run_if(
+                            resource_exists::<
+                                Events<WindowFocused>,
+                            >(),
+                        ),
+                    )
+                        .run_if(in_state(GameState::Playing)),
+                    (pause_input, update_pause_buttons)
+                        .chain()
+                        .run_if(in_state(GameState::Paused)),
+                    (controls_input, update_controls_screen)
+                        .chain()
+                        .run_if(in_state(GameState::Controls)),
+                    level_cleared_input.run_if(in_state(
+                        GameState::LevelCleared,
+                    )),
+                    high_score_input
+                        .run_if(in_state(GameState::HighScores)),
+                    // The key that saves the initials may also be bound
+                    // to an action
+                    game_over_input
+                        .before(type_initials)
+                        .run_if(in_state(GameState::GameOver)),
+                    update_game_over_screen.run_if(
+                        in_state(GameState::GameOver).and_then(
+                            resource_exists_and_changed::<
+                                InitialsEntry,
+                            >(),
+                        ),
+                    ),
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct MainMenuScreen;
+
+#[derive(Component)]
+pub struct PauseScreen;
+
+/// A button of the pause screen, and what it does.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseButton {
+    Resume,
+    RestartLevel,
+    Settings,
+    QuitToMenu,
+}
+
+impl PauseButton {
+    /// Every button, from top to bottom.
+    pub const ALL: [PauseButton; 4] = [
+        PauseButton::Resume,
+        PauseButton::RestartLevel,
+        PauseButton::Settings,
+        PauseButton::QuitToMenu,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::RestartLevel => "Restart level",
+            PauseButton::Settings => "Settings",
+            PauseButton::QuitToMenu => "Quit to menu",
+        }
+    }
+
+    /// Name of the key that presses the button.
+    pub fn key_name(self, config: &GameConfig) -> String {
+        match self {
+            PauseButton::Resume => {
+                config.bindings.key_name(Action::Pause)
+            }
+            PauseButton::RestartLevel => {
+                config.bindings.key_name(Action::Restart)
+            }
+            PauseButton::Settings => format!("{CONTROLS_KEY:?}"),
+            PauseButton::QuitToMenu => {
+                config.bindings.key_name(Action::Quit)
+            }
+        }
+    }
+}
+
+/// The button chosen on the pause screen.
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    /// Index of the chosen button in [`PauseButton::ALL`].
+    pub selected: usize,
+}
+
+#[derive(Component)]
+pub struct ControlsScreen;
+
+#[derive(Component)]
+pub struct HighScoreScreen;
+
+#[derive(Component)]
+pub struct LevelClearedScreen;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+#[derive(Resource)]
+pub struct LevelClearedTimer(pub Timer);
+
+/// Where the player is on the controls screen.
+#[derive(Resource)]
+pub struct ControlsMenu {
+    /// Screen to go back to when leaving.
+    pub return_to: GameState,
+    /// Index of the chosen action in [`Action::ALL`].
+    pub selected: usize,
+    /// Whether the next key pressed gets bound to the chosen action.
+    pub rebinding: bool,
+    /// Outcome of the last rebinding, shown below the actions.
+    pub message: Option<String>,
+}
+
+impl ControlsMenu {
+    pub fn new(return_to: GameState) -> Self {
+        ControlsMenu {
+            return_to,
+            selected: 0,
+            rebinding: false,
+            message: None,
+        }
+    }
+
+    pub fn action(&self) -> Action {
+        Action::ALL[self.selected]
+    }
+}
+
+/// Spawns a full-screen overlay with a title and a few lines of text
+/// below it, returning the overlay to add more to.
+fn spawn_screen(
+    commands: &mut Commands,
+    config: &GameConfig,
+    marker: impl Component,
+    title: &str,
+    lines: &[String],
+) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: MENU_ROW_GAP,
+                    ..default()
+                },
+                background_color: OVERLAY_COLOR.into(),
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: MENU_TITLE_FONT_SIZE,
+                    color: config.text_color,
+                    ..default()
+                },
+            ));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font_size: MENU_TEXT_FONT_SIZE,
+                        color: config.text_color,
+                        ..default()
+                    },
+                ));
+            }
+        })
+        .id()
+}
+
+pub fn setup_main_menu(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+) {
+    let bindings = &config.bindings;
+    spawn_screen(
+        &mut commands,
+        &config,
+        MainMenuScreen,
+        "Breakout",
+        &[
+            format!(
+                "Press {} to start",
+                bindings.key_name(Action::Launch)
+            ),
+            format!(
+                "Press {CONTROLS_KEY:?} to change the controls"
+            ),
+            format!(
+                "Press {HIGH_SCORES_KEY:?} to see the high scores"
+            ),
+            format!(
+                "Press {} to quit",
+                bindings.key_name(Action::Quit)
+            ),
+        ],
+    );
+}
+
+pub fn setup_pause_screen(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut pause_menu: ResMut<PauseMenu>,
+) {
+    pause_menu.selected = 0;
+    let screen = spawn_screen(
+        &mut commands,
+        &config,
+        PauseScreen,
+        "Paused",
+        &[],
+    );
+    commands.entity(screen).with_children(|parent| {
+        for (index, button) in
+            PauseButton::ALL.into_iter().enumerate()
+        {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: BUTTON_WIDTH,
+                            padding: BUTTON_PADDING,
+                            justify_content:
+                                JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: if index
+                            == pause_menu.selected
+                        {
+                            SELECTED_BUTTON_COLOR
+                        } else {
+                            BUTTON_COLOR
+                        }
+                        .into(),
+                        ..default()
+                    },
+                    button,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        format!(
+                            "{} ({})",
+                            button.label(),
+                            button.key_name(&config)
+                        ),
+                        TextStyle {
+                            font_size: MENU_TEXT_FONT_SIZE,
+                            color: config.text_color,
+                            ..default()
+                        },
+                    ));
+                });
+        }
+    });
+}
+
+pub fn setup_level_cleared_screen(
+    mut commands: Commands,
+    scoreboard: Res<ScoreBoard>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    config: Res<GameConfig>,
+) {
+    let next_level = level_assets
+        .get(&levels.0[current_level.0 + 1])
+        .map_or("", |level| level.name.as_str());
+
+    spawn_screen(
+        &mut commands,
+        &config,
+        LevelClearedScreen,
+        &format!("Level {} Cleared", current_level.0 + 1),
+        &[
+            format!("Score: {}", scoreboard.score),
+            format!("Next up: {next_level}"),
+            format!(
+                "Press {} to continue",
+                config.bindings.key_name(Action::Launch)
+            ),
+        ],
+    );
+    commands.insert_resource(LevelClearedTimer(
+        Timer::from_seconds(
+            LEVEL_CLEARED_DELAY,
+            TimerMode::Once,
+        ),
+    ));
+}
+
+pub fn setup_game_over_screen(
+    mut commands: Commands,
+    scoreboard: Res<ScoreBoard>,
+    lives: Res<Lives>,
+    config: Res<GameConfig>,
+    initials_entry: Option<Res<InitialsEntry>>,
+) {
+    // The run also ends when the last level is cleared
+    let title = if lives.remaining > 0 {
+        "You Win!"
+    } else {
+        "Game Over"
+    };
+
+    let mut lines =
+        vec![format!("Final score: {}", scoreboard.score)];
+    match initials_entry
+        .as_deref()
+        .filter(|entry| entry.pending.is_some())
+    {
+        Some(entry) => lines.extend([
+            "New high score! Type your initials:".to_owned(),
+            format!("{:_<INITIALS_LENGTH$}", entry.initials),
+            format!("Press {SAVE_INITIALS_KEY:?} to save"),
+        ]),
+        None => lines.extend([
+            format!(
+                "Press {} to play again",
+                config.bindings.key_name(Action::Launch)
+            ),
+            format!(
+                "Press {} to return to the menu",
+                config.bindings.key_name(Action::Quit)
+            ),
+        ]),
+    }
+
+    spawn_screen(
+        &mut commands,
+        &config,
+        GameOverScreen,
+        title,
+        &lines,
+    );
+}
+
+/// Draws the game over screen again as initials are typed and saved.
+pub fn update_game_over_screen(
+    mut commands: Commands,
+    scoreboard: Res<ScoreBoard>,
+    lives: Res<Lives>,
+    config: Res<GameConfig>,
+    initials_entry: Option<Res<InitialsEntry>>,
+    screen_query: Query<Entity, With<GameOverScreen>>,
+) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    setup_game_over_screen(
+        commands,
+        scoreboard,
+        lives,
+        config,
+        initials_entry,
+    );
+}
+
+pub fn setup_high_score_screen(
+    mut commands: Commands,
+    high_scores: Option<Res<HighScores>>,
+    config: Res<GameConfig>,
+) {
+    let entries = high_scores
+        .as_deref()
+        .map_or(&[][..], |high_scores| &high_scores.entries);
+    let mut lines: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .map(|(rank, entry)| {
+            format!(
+                "{}. {:<INITIALS_LENGTH$}  {:>7}  Level {}  {}  {}",
+                rank + 1,
+                entry.name,
+                entry.score,
+                entry.level,
+                entry.mode.label(),
+                entry.date
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push("No high scores yet".to_owned());
+    }
+    lines.push(format!("Press {BACK_KEY:?} to go back"));
+
+    spawn_screen(
+        &mut commands,
+        &config,
+        HighScoreScreen,
+        "High Scores",
+        &lines,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn main_menu_input(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    config_handle: Res<ConfigHandle>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let bindings = &config.bindings;
+    // Starting a game needs the level layouts and settings, which
+    // load in the background while the menu is shown
+    if bindings.just_pressed(&keyboard, Action::Launch)
+        && levels.all_loaded(&level_assets)
+        && config_handle.ready
+    {
+        next_state.set(GameState::Playing);
+    } else if keyboard.just_pressed(CONTROLS_KEY) {
+        commands.insert_resource(ControlsMenu::new(
+            GameState::MainMenu,
+        ));
+        next_state.set(GameState::Controls);
+    } else if keyboard.just_pressed(HIGH_SCORES_KEY) {
+        next_state.set(GameState::HighScores);
+    } else if bindings.just_pressed(&keyboard, Action::Quit) {
+        app_exit_events.send(AppExit);
+    }
+}
+
+/// Starts a game as soon as the levels and settings are ready, for
+/// when nobody is there to start it from the menu.
+pub fn skip_main_menu(
+    levels: Res<Levels>,
+    level_assets: Res<Assets<Level>>,
+    config_handle: Res<ConfigHandle>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if levels.all_loaded(&level_assets) && config_handle.ready {
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn playing_input(
+    keyboard: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if config.bindings.just_pressed(&keyboard, Action::Pause) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Presses the pause screen's buttons, by clicking them, with their
+/// keys, or by choosing them with the arrow keys or d-pad.
+#[allow(clippy::too_many_arguments)]
+pub fn pause_input(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    config: Res<GameConfig>,
+    interaction_query: Query<
+        (&Interaction, &PauseButton),
+        Changed<Interaction>,
+    >,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut restart_level_events: EventWriter<RestartLevelEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let bindings = &config.bindings;
+    let gamepad_pressed = |button_type| {
+        gamepads.iter().any(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton::new(
+                gamepad,
+                button_type,
+            ))
+        })
+    };
+    let count = PauseButton::ALL.len();
+
+    let mut pressed = None;
+    for (interaction, &button) in &interaction_query {
+        match interaction {
+            Interaction::Pressed => pressed = Some(button),
+            Interaction::Hovered => {
+                pause_menu.selected = PauseButton::ALL
+                    .iter()
+                    .position(|&other| other == button)
+                    .unwrap_or_default();
+            }
+            Interaction::None => {}
+        }
+    }
+
+    if keyboard.just_pressed(MENU_PREVIOUS_KEY)
+        || gamepad_pressed(GamepadButtonType::DPadUp)
+    {
+        pause_menu.selected =
+            (pause_menu.selected + count - 1) % count;
+    } else if keyboard.just_pressed(MENU_NEXT_KEY)
+        || gamepad_pressed(GamepadButtonType::DPadDown)
+    {
+        pause_menu.selected = (pause_menu.selected + 1) % count;
+    } else if keyboard.just_pressed(MENU_SELECT_KEY)
+        || gamepad_pressed(GamepadButtonType::South)
+    {
+        pressed = Some(PauseButton::ALL[pause_menu.selected]);
+    } else if bindings.just_pressed(&keyboard, Action::Pause) {
+        pressed = Some(PauseButton::Resume);
+    } else if bindings.just_pressed(&keyboard, Action::Restart) {
+        pressed = Some(PauseButton::RestartLevel);
+    } else if keyboard.just_pressed(CONTROLS_KEY) {
+        pressed = Some(PauseButton::Settings);
+    } else if bindings.just_pressed(&keyboard, Action::Quit) {
+        pressed = Some(PauseButton::QuitToMenu);
+    }
+
+    match pressed {
+        Some(PauseButton::Resume) => {
+            next_state.set(GameState::Playing);
+        }
+        Some(PauseButton::RestartLevel) => {
+            restart_level_events.send_default();
+            next_state.set(GameState::Playing);
+        }
+        Some(PauseButton::Settings) => {
+            commands.insert_resource(ControlsMenu::new(
+                GameState::Paused,
+            ));
+            next_state.set(GameState::Controls);
+        }
+        Some(PauseButton::QuitToMenu) => {
+            next_state.set(GameState::MainMenu);
+        }
+        None => {}
+    }
+}
+
+/// Highlights the chosen button of the pause screen.
+pub fn update_pause_buttons(
+    pause_menu: Res<PauseMenu>,
+    mut button_query: Query<(
+        &PauseButton,
+        &mut BackgroundColor,
+    )>,
+) {
+    if !pause_menu.is_changed() {
+        return;
+    }
+    for (&button, mut background_color) in &mut button_query {
+        let selected =
+            PauseButton::ALL[pause_menu.selected] == button;
+        background_color.0 = if selected {
+            SELECTED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+    }
+}
+
+/// Pauses the game when the player switches to another window.
+pub fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if focus_events.iter().any(|event| !event.focused) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Moves between the actions on the controls screen and binds keys
+/// to them, refusing keys already bound to another action. Every
+/// change is saved to the user settings.
+pub fn controls_input(
+    keyboard: Res<Input<KeyCode>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut config: ResMut<GameConfig>,
+    source: Res<ConfigSource>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if menu.rebinding {
+        let Some(&key) = keyboard.get_just_pressed().next()
+        else {
+            return;
+        };
+        let action = menu.action();
+        match config.bindings.action(key) {
+            Some(other) if other != action => {
+                menu.message = Some(format!(
+                    "{key:?} is already bound to {}",
+                    other.label()
+                ));
+                return;
+            }
+            Some(_) => {
+                menu.message = None;
+            }
+            None => {
+                config.bindings.set(action, key);
+                menu.message =
+                    Some(save_bindings(&config, &source));
+            }
+        }
+        menu.rebinding = false;
+    } else if keyboard.just_pressed(MENU_PREVIOUS_KEY) {
+        menu.selected = (menu.selected + Action::ALL.len() - 1)
+            % Action::ALL.len();
+    } else if keyboard.just_pressed(MENU_NEXT_KEY) {
+        menu.selected = (menu.selected + 1) % Action::ALL.len();
+    } else if keyboard.just_pressed(MENU_SELECT_KEY) {
+        menu.rebinding = true;
+        menu.message = None;
+    } else if keyboard.just_pressed(CONTROLS_RESET_KEY) {
+        config.bindings = default();
+        menu.message = Some(save_bindings(&config, &source));
+    } else if keyboard.just_pressed(BACK_KEY) {
+        next_state.set(menu.return_to);
+    }
+}
+
+/// Writes the key bindings to the user settings, returning what
+/// happened for the controls screen to show.
+fn save_bindings(
+    config: &GameConfig,
+    source: &ConfigSource,
+) -> String {
+    let Some(path) = &source.user_settings else {
+        return "No user data folder to save the controls to"
+            .to_owned();
+    };
+    match save_setting(
+        path,
+        "bindings",
+        config.bindings.to_value(),
+    ) {
+        Ok(()) => format!("Saved to {}", path.display()),
+        Err(error) => {
+            error!("Failed to save the controls to {path:?}: {error}");
+            format!("Could not save the controls: {error}")
+        }
+    }
+}
+
+/// Draws the controls screen again whenever it or the bindings
+/// change.
+pub fn update_controls_screen(
+    mut commands: Commands,
+    menu: Res<ControlsMenu>,
+    config: Res<GameConfig>,
+    screen_query: Query<Entity, With<ControlsScreen>>,
+) {
+    if !menu.is_changed() && !config.is_changed() {
+        return;
+    }
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut lines: Vec<String> = Action::ALL
+        .into_iter()
+        .enumerate()
+        .map(|(i, action)| {
+            let key = if menu.rebinding && i == menu.selected {
+                "press a key".to_owned()
+            } else {
+                config.bindings.key_name(action)
+            };
+            let cursor =
+                if i == menu.selected { "> " } else { "" };
+            format!("{cursor}{}: {key}", action.label())
+        })
+        .collect();
+    lines.push(menu.message.clone().unwrap_or_else(|| {
+        format!(
+            "{MENU_PREVIOUS_KEY:?}/{MENU_NEXT_KEY:?} to \
+             choose, {MENU_SELECT_KEY:?} to rebind, \
+             {CONTROLS_RESET_KEY:?} to reset, \
+             {BACK_KEY:?} to go back"
+        )
+    }));
+
+    spawn_screen(
+        &mut commands,
+        &config,
+        ControlsScreen,
+        "Controls",
+        &lines,
+    );
+}
+
+pub fn high_score_input(
+    keyboard: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(BACK_KEY) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+pub fn level_cleared_input(
+    keyboard: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+    mut timer: ResMut<LevelClearedTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if config.bindings.just_pressed(&keyboard, Action::Launch)
+        || timer.0.tick(time.delta()).just_finished()
+    {
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn game_over_input(
+    keyboard: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    initials_entry: Option<Res<InitialsEntry>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Typed initials aren't actions
+    if initials_entry
+        .is_some_and(|entry| entry.pending.is_some())
+    {
+        return;
+    }
+    let bindings = &config.bindings;
+    if bindings.just_pressed(&keyboard, Action::Launch)
+        || bindings.just_pressed(&keyboard, Action::Restart)
+    {
+        next_state.set(GameState::Playing);
+    } else if bindings.just_pressed(&keyboard, Action::Quit) {
+        next_state.set(GameState::MainMenu);
+    }
+}
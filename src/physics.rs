@@ -1,27 +1,299 @@
-use bevy::{
-    prelude::*,
-    sprite::collide_aabb::{collide, Collision},
-};
+use bevy::prelude::*;
 
 use crate::{
-    components::{ball::Ball, brick::Brick},
-    ui::ScoreBoard,
+    components::{
+        ball::{Ball, StuckToPaddle},
+        brick::{Brick, BrickHit, BrickType},
+        paddle::{move_paddle, Paddle},
+        wall::WallLocation,
+    },
+    config::GameConfig,
+    game::GameplayStage,
+    powerup::{ActiveEffects, PowerUpKind},
 };
 
-#[derive(Event, Default)]
-pub struct CollisionEvent;
+/// Moves balls through the arena and bounces them off its colliders.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CollisionEvent>()
+            .add_event::<BrickDestroyedEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_velocity
+                        .before(move_paddle)
+                        .in_set(GameplayStage::Movement),
+                    (
+                        check_for_collisions,
+                        check_for_ball_collisions,
+                    )
+                        .chain()
+                        .in_set(GameplayStage::Collision),
+                ),
+            );
+    }
+}
 
+/// What a ball ran into.
+#[derive(Debug, Clone, Copy)]
+pub enum ColliderKind {
+    Wall(WallLocation),
+    Paddle,
+    /// A brick, as it was left by the hit.
+    Brick {
+        brick_type: BrickType,
+        hit: BrickHit,
+    },
+    Ball,
+    /// A collider that is none of the above.
+    Other,
+}
+
+/// Sent whenever a ball bounces off a collider or another ball.
+#[derive(Event, Debug, Clone)]
+pub struct CollisionEvent {
+    pub ball: Entity,
+    /// The collider, or the other ball.
+    pub collider: Entity,
+    pub kind: ColliderKind,
+    /// Where the ball touched the collider.
+    pub point: Vec2,
+    /// Normal of the collider's surface at `point`, pointing towards
+    /// the ball.
+    pub normal: Vec2,
+    /// Speed at which the ball was moving into the collider.
+    pub impact_speed: f32,
+}
+
+/// Sent when a ball breaks a brick, with where the brick was.
+#[derive(Event)]
+pub struct BrickDestroyedEvent {
+    pub entity: Entity,
+    pub position: Vec2,
+    /// What the brick was worth.
+    pub points: usize,
+    /// Speed of the ball that broke it.
+    pub ball_speed: f32,
+}
+
+/// Marks anything balls bounce off.
 #[derive(Component)]
 pub struct Collider;
 
-#[derive(Resource)]
-pub struct CollisionSound(pub Handle<AudioSource>);
-
+/// Distance moved per second.
 #[derive(Component, Deref, DerefMut)]
 pub struct Velocity(pub Vec2);
 
+/// Length of a fixed step, in seconds.
+pub const TIME_STEP: f32 = 1.0 / 60.0;
+/// Upper bound on the number of surfaces a ball can bounce off within
+/// a single fixed step.
+pub const MAX_COLLISIONS_PER_STEP: usize = 4;
+// Distance a ball is pushed back from a surface it bounced off, so it
+// doesn't start the rest of its motion touching that surface
+pub const CONTACT_OFFSET: f32 = 0.01;
+
+/// How a ball overlaps a collider.
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    /// Direction pushing the ball out of the collider.
+    pub normal: Vec2,
+    /// Distance the ball has to move along `normal` to stop overlapping.
+    pub depth: f32,
+}
+
+/// Where along its motion a swept ball first touches a collider.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepHit {
+    /// Fraction of the motion travelled before the contact, in `[0, 1]`.
+    pub time: f32,
+    /// Normal of the collider's surface at the contact.
+    pub normal: Vec2,
+    /// How far the ball was already embedded in the collider.
+    pub depth: f32,
+}
+
+/// Tests a circle against a box, both given by their centers.
+pub fn circle_aabb_contact(
+    circle_center: Vec2,
+    radius: f32,
+    box_center: Vec2,
+    box_half_size: Vec2,
+) -> Option<Contact> {
+    let min = box_center - box_half_size;
+    let max = box_center + box_half_size;
+    let closest = circle_center.clamp(min, max);
+    let offset = circle_center - closest;
+    let distance_squared = offset.length_squared();
+
+    if distance_squared > radius * radius {
+        return None;
+    }
+
+    if distance_squared > 0.0 {
+        let distance = distance_squared.sqrt();
+        return Some(Contact {
+            normal: offset / distance,
+            depth: radius - distance,
+        });
+    }
+
+    // The center is inside the box, so push it out through the
+    // nearest face
+    let faces = [
+        (circle_center.x - min.x, Vec2::NEG_X),
+        (max.x - circle_center.x, Vec2::X),
+        (circle_center.y - min.y, Vec2::NEG_Y),
+        (max.y - circle_center.y, Vec2::Y),
+    ];
+    let (distance, normal) = faces
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .unwrap();
+
+    Some(Contact {
+        normal,
+        depth: radius + distance,
+    })
+}
+
+/// First time in `[0, 1]` at which a point moving along `motion`
+/// enters a circle.
+fn ray_circle(
+    start: Vec2,
+    motion: Vec2,
+    center: Vec2,
+    radius: f32,
+) -> Option<f32> {
+    let offset = start - center;
+    let a = motion.length_squared();
+    let b = offset.dot(motion);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - a * c;
+
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let time = (-b - discriminant.sqrt()) / a;
+    (0.0..=1.0).contains(&time).then_some(time)
+}
+
+/// Time at which a point moving along `motion` enters a box, with the
+/// normal of the face it enters through. The time is negative if the
+/// point starts inside the box.
+fn ray_aabb(
+    start: Vec2,
+    motion: Vec2,
+    min: Vec2,
+    max: Vec2,
+) -> Option<(f32, Vec2)> {
+    let mut time_of_entry = f32::NEG_INFINITY;
+    let mut time_of_exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    for axis in 0..2 {
+        if motion[axis] == 0.0 {
+            // Moving parallel to this axis' slab, so the point must
+            // already be within it
+            if start[axis] < min[axis] || start[axis] > max[axis]
+            {
+                return None;
+            }
+            continue;
+        }
+
+        let near = if motion[axis] > 0.0 { min } else { max };
+        let far = if motion[axis] > 0.0 { max } else { min };
+        let entry = (near[axis] - start[axis]) / motion[axis];
+        let exit = (far[axis] - start[axis]) / motion[axis];
+
+        if entry > time_of_entry {
+            time_of_entry = entry;
+            normal = Vec2::ZERO;
+            normal[axis] = -motion[axis].signum();
+        }
+        time_of_exit = time_of_exit.min(exit);
+    }
+
+    if time_of_entry > time_of_exit
+        || time_of_entry > 1.0
+        || time_of_exit < 0.0
+    {
+        return None;
+    }
+
+    Some((time_of_entry, normal))
+}
+
+/// Sweeps a circle starting at `start` along `motion` against a static
+/// box, returning the first contact, if any.
+///
+/// A circle that already overlaps the box while moving into it hits it
+/// at time 0, with the depth it has to be pushed out by.
+pub fn sweep_circle(
+    start: Vec2,
+    motion: Vec2,
+    radius: f32,
+    box_center: Vec2,
+    box_half_size: Vec2,
+) -> Option<SweepHit> {
+    if let Some(contact) = circle_aabb_contact(
+        start,
+        radius,
+        box_center,
+        box_half_size,
+    ) {
+        return (motion.dot(contact.normal) < 0.0).then_some(
+            SweepHit {
+                time: 0.0,
+                normal: contact.normal,
+                depth: contact.depth,
+            },
+        );
+    }
+
+    // Sweeping a circle against a box is the same as sweeping its
+    // center against the box grown by the radius, with rounded
+    // corners. Try the grown box first, then the corner's circle if
+    // that's where the center touches it.
+    let min = box_center - box_half_size;
+    let max = box_center + box_half_size;
+    let (time, normal) =
+        ray_aabb(start, motion, min - radius, max + radius)?;
+
+    let point = start + motion * time.max(0.0);
+    let outside_x = point.x < min.x || point.x > max.x;
+    let outside_y = point.y < min.y || point.y > max.y;
+    if outside_x && outside_y {
+        let corner = Vec2::new(
+            if point.x < min.x { min.x } else { max.x },
+            if point.y < min.y { min.y } else { max.y },
+        );
+        let time = ray_circle(start, motion, corner, radius)?;
+        let normal =
+            (start + motion * time - corner).normalize();
+        // Only grazing the corner, without moving into it
+        return (motion.dot(normal) < 0.0).then_some(SweepHit {
+            time,
+            normal,
+            depth: 0.0,
+        });
+    }
+
+    Some(SweepHit {
+        time,
+        normal,
+        depth: 0.0,
+    })
+}
+
+/// Moves everything with a [`Velocity`] except balls, which are moved
+/// by [`check_for_collisions`] so they can't pass through colliders.
 pub fn apply_velocity(
-    mut query: Query<(&mut Transform, &Velocity)>,
+    mut query: Query<(&mut Transform, &Velocity), Without<Ball>>,
     time_step: Res<FixedTime>,
 ) {
     for (mut transform, velocity) in &mut query {
@@ -32,91 +304,412 @@ pub fn apply_velocity(
     }
 }
 
+/// Moves every ball through the fixed step, bouncing it off the
+/// earliest collider in its path and continuing with the remaining
+/// motion.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn check_for_collisions(
     mut commands: Commands,
-    mut scoreboard: ResMut<ScoreBoard>,
+    effects: Res<ActiveEffects>,
     mut ball_query: Query<
-        (&mut Velocity, &Transform),
-        With<Ball>,
+        (Entity, &mut Velocity, &mut Transform),
+        (With<Ball>, Without<Collider>, Without<StuckToPaddle>),
     >,
-    collider_query: Query<
-        (Entity, &Transform, Option<&Brick>),
+    mut collider_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&mut Brick>,
+            Option<&Paddle>,
+            Option<&WallLocation>,
+        ),
         With<Collider>,
     >,
     mut collision_events: EventWriter<CollisionEvent>,
+    mut brick_destroyed_events: EventWriter<BrickDestroyedEvent>,
+    time_step: Res<FixedTime>,
+    config: Res<GameConfig>,
 ) {
-    let (mut ball_velocity, ball_transform) =
-        ball_query.single_mut();
-    let ball_size = ball_transform.scale.truncate();
+    let fireball = effects.is_active(PowerUpKind::Fireball);
+    let sticky = effects.is_active(PowerUpKind::StickyPaddle);
 
-    // check collision with walls
-    for (collider_entity, transform, maybe_brick) in
-        &collider_query
+    // Bricks destroyed this step are only despawned once the commands
+    // are applied, so they have to be skipped by hand until then. This
+    // is shared between balls, so two balls can't both break a brick.
+    let mut destroyed = Vec::new();
+
+    for (ball_entity, mut ball_velocity, mut ball_transform) in
+        &mut ball_query
     {
-        let collision = collide(
-            ball_transform.translation,
-            ball_size,
-            transform.translation,
-            transform.scale.truncate(),
-        );
-        if let Some(collision) = collision {
-            // Sends a collision event so that other systems can react to the collision
-            collision_events.send_default();
-
-            // Bricks should be despawned and increment the scoreboard on collision
-            if maybe_brick.is_some() {
-                scoreboard.score += 1;
-                commands.entity(collider_entity).despawn();
+        let ball_radius = ball_transform.scale.x / 2.0;
+        let mut remaining_time = time_step.period.as_secs_f32();
+
+        // Anything the ball overlaps while moving away from it,
+        // such as a paddle that slid into its side, won't be hit by
+        // the sweep below, so push the ball out of it directly
+        for (entity, transform, ..) in &collider_query {
+            if destroyed.contains(&entity) {
+                continue;
+            }
+            if let Some(contact) = circle_aabb_contact(
+                ball_transform.translation.truncate(),
+                ball_radius,
+                transform.translation.truncate(),
+                transform.scale.truncate() / 2.0,
+            ) {
+                if ball_velocity.dot(contact.normal) >= 0.0 {
+                    ball_transform.translation +=
+                        (contact.normal * contact.depth)
+                            .extend(0.0);
+                }
             }
+        }
 
-            // reflect the ball when it collides
-            let mut reflect_x = false;
-            let mut reflect_y = false;
+        for _ in 0..MAX_COLLISIONS_PER_STEP {
+            let position = ball_transform.translation.truncate();
+            let motion = ball_velocity.0 * remaining_time;
 
-            // only reflect if the ball's velocity is going in the opposite direction of the
-            // collision
-            match collision {
-                Collision::Left => {
-                    reflect_x = ball_velocity.x > 0.0
-                }
-                Collision::Right => {
-                    reflect_x = ball_velocity.x < 0.0
-                }
-                Collision::Top => {
-                    reflect_y = ball_velocity.y < 0.0
+            let earliest_hit = collider_query
+                .iter()
+                .filter(|(entity, ..)| {
+                    !destroyed.contains(entity)
+                })
+                .filter_map(|(entity, transform, ..)| {
+                    sweep_circle(
+                        position,
+                        motion,
+                        ball_radius,
+                        transform.translation.truncate(),
+                        transform.scale.truncate() / 2.0,
+                    )
+                    // Moving away from a surface is not a collision
+                    .filter(|hit| motion.dot(hit.normal) < 0.0)
+                    .map(|hit| (entity, hit))
+                })
+                .min_by(|(_, a), (_, b)| {
+                    a.time.total_cmp(&b.time)
+                });
+
+            let Some((collider_entity, hit)) = earliest_hit
+            else {
+                ball_transform.translation += motion.extend(0.0);
+                break;
+            };
+
+            let point = position + motion * hit.time
+                - hit.normal * ball_radius;
+            let impact_speed = -ball_velocity.dot(hit.normal);
+
+            // Advance to the contact, then step back off the
+            // surface, pushing the ball out first if it was embedded
+            ball_transform.translation += (motion * hit.time
+                + hit.normal * (hit.depth + CONTACT_OFFSET))
+                .extend(0.0);
+            remaining_time *= 1.0 - hit.time;
+
+            let (
+                _,
+                transform,
+                maybe_brick,
+                maybe_paddle,
+                maybe_wall,
+            ) = collider_query.get_mut(collider_entity).unwrap();
+
+            // Bricks take damage and, once broken, are despawned and
+            // reported along with their value. A fireball breaks any
+            // breakable brick outright and carries on through it.
+            let mut pierced = false;
+            let kind = if let Some(mut brick) = maybe_brick {
+                pierced = fireball && brick.is_breakable();
+                let brick_hit = if pierced {
+                    BrickHit::Destroyed
+                } else {
+                    brick.hit()
+                };
+                if brick_hit == BrickHit::Destroyed {
+                    commands.entity(collider_entity).despawn();
+                    destroyed.push(collider_entity);
+                    brick_destroyed_events.send(
+                        BrickDestroyedEvent {
+                            entity: collider_entity,
+                            position: transform
+                                .translation
+                                .truncate(),
+                            points: brick.points(),
+                            ball_speed: ball_velocity.length(),
+                        },
+                    );
                 }
-                Collision::Bottom => {
-                    reflect_y = ball_velocity.y > 0.0
+                ColliderKind::Brick {
+                    brick_type: brick.0,
+                    hit: brick_hit,
                 }
-                Collision::Inside => { /* do nothing */ }
-            }
+            } else if maybe_paddle.is_some() {
+                ColliderKind::Paddle
+            } else if let Some(&location) = maybe_wall {
+                ColliderKind::Wall(location)
+            } else {
+                ColliderKind::Other
+            };
 
-            // reflect velocity on the x-axis if we hit something on the x-axis
-            if reflect_x {
-                ball_velocity.x = -ball_velocity.x;
+            // Sends a collision event so that other systems can react
+            // to what was hit
+            collision_events.send(CollisionEvent {
+                ball: ball_entity,
+                collider: collider_entity,
+                kind,
+                point,
+                normal: hit.normal,
+                impact_speed,
+            });
+            if pierced {
+                continue;
             }
 
-            // reflect velocity on the y-axis if we hit something on the y-axis
-            if reflect_y {
-                ball_velocity.y = -ball_velocity.y;
+            // The paddle sends the ball off at an angle that depends
+            // on where it was hit, keeping the ball's speed. A sticky
+            // paddle holds on to the ball until it is launched.
+            if let Some(paddle) = maybe_paddle {
+                if hit.normal.y > 0.0 {
+                    ball_velocity.0 = paddle.rebound_direction(
+                        transform,
+                        ball_transform.translation.x,
+                        &config,
+                    ) * ball_velocity.length();
+                    if sticky {
+                        commands.entity(ball_entity).insert(
+                            StuckToPaddle {
+                                offset: (ball_transform
+                                    .translation
+                                    - transform.translation)
+                                    .truncate(),
+                            },
+                        );
+                        break;
+                    }
+                    continue;
+                }
             }
+
+            // reflect velocity about the normal of the surface that
+            // was hit, which for corners points away from the corner
+            let reflection =
+                2.0 * ball_velocity.dot(hit.normal) * hit.normal;
+            ball_velocity.0 -= reflection;
         }
     }
 }
 
-pub fn play_collision_sound(
-    mut commands: Commands,
-    mut collision_events: EventReader<CollisionEvent>,
-    sound: Res<CollisionSound>,
+/// Bounces balls off each other as equally heavy, perfectly elastic
+/// circles.
+#[allow(clippy::type_complexity)]
+pub fn check_for_ball_collisions(
+    mut ball_query: Query<
+        (Entity, &mut Velocity, &mut Transform),
+        (With<Ball>, Without<StuckToPaddle>),
+    >,
+    mut collision_events: EventWriter<CollisionEvent>,
 ) {
-    // Play a sound once per frame if a collision occurred.
-    if !collision_events.is_empty() {
-        // This prevents events staying active on the next frame.
-        collision_events.clear();
-        commands.spawn(AudioBundle {
-            source: sound.0.clone(),
-            // auto-despawn the entity when playback finishes
-            settings: PlaybackSettings::DESPAWN,
-        });
+    let mut combinations =
+        ball_query.iter_combinations_mut::<2>();
+    while let Some(
+        [(ball_a, mut velocity_a, mut transform_a), (ball_b, mut velocity_b, mut transform_b)],
+    ) = combinations.fetch_next()
+    {
+        let offset = transform_b.translation.truncate()
+            - transform_a.translation.truncate();
+        let min_distance =
+            (transform_a.scale.x + transform_b.scale.x) / 2.0;
+        let distance = offset.length();
+        if distance >= min_distance || distance == 0.0 {
+            continue;
+        }
+
+        // Separate the balls evenly so they no longer overlap
+        let normal = offset / distance;
+        let correction =
+            normal * (min_distance - distance) / 2.0;
+        transform_a.translation -= correction.extend(0.0);
+        transform_b.translation += correction.extend(0.0);
+
+        // Only exchange momentum if they are moving towards each other
+        let closing_speed =
+            (velocity_a.0 - velocity_b.0).dot(normal);
+        if closing_speed <= 0.0 {
+            continue;
+        }
+
+        // Equal masses swap their velocity along the normal
+        velocity_a.0 -= normal * closing_speed;
+        velocity_b.0 += normal * closing_speed;
+
+        // Each ball hit the other
+        let point = transform_a.translation.truncate()
+            + normal * transform_a.scale.x / 2.0;
+        for (ball, collider, normal) in
+            [(ball_a, ball_b, -normal), (ball_b, ball_a, normal)]
+        {
+            collision_events.send(CollisionEvent {
+                ball,
+                collider,
+                kind: ColliderKind::Ball,
+                point,
+                normal,
+                impact_speed: closing_speed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.distance(expected) < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_thin_brick() {
+        // Far more than the brick's height in a single step
+        let hit = sweep_circle(
+            Vec2::new(0.0, -100.0),
+            Vec2::new(0.0, 1000.0),
+            10.0,
+            Vec2::ZERO,
+            Vec2::new(50.0, 2.0),
+        )
+        .unwrap();
+        assert_near(hit.normal, Vec2::NEG_Y);
+        assert!((hit.time - 0.088).abs() < 1e-4);
+        assert_eq!(hit.depth, 0.0);
+    }
+
+    #[test]
+    fn corner_hit_has_diagonal_normal() {
+        let hit = sweep_circle(
+            Vec2::new(-30.0, -30.0),
+            Vec2::new(40.0, 40.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::splat(10.0),
+        )
+        .unwrap();
+        assert_near(
+            hit.normal,
+            Vec2::splat(-std::f32::consts::FRAC_1_SQRT_2),
+        );
+        // Stops 5 units short of the corner
+        let center = Vec2::new(-30.0, -30.0)
+            + Vec2::new(40.0, 40.0) * hit.time;
+        assert!(
+            (center.distance(Vec2::splat(-10.0)) - 5.0).abs()
+                < 1e-3
+        );
+    }
+
+    #[test]
+    fn embedded_circle_is_pushed_out_of_nearest_face() {
+        let contact = circle_aabb_contact(
+            Vec2::new(2.0, 1.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::new(10.0, 4.0),
+        )
+        .unwrap();
+        assert_near(contact.normal, Vec2::Y);
+        assert!((contact.depth - 8.0).abs() < 1e-4);
+
+        // Moving into the box hits straight away, moving out doesn't
+        let hit = sweep_circle(
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, -10.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::new(10.0, 4.0),
+        )
+        .unwrap();
+        assert_eq!(hit.time, 0.0);
+        assert!((hit.depth - 8.0).abs() < 1e-4);
+        assert!(sweep_circle(
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 10.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::new(10.0, 4.0),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn overlapping_circle_is_pushed_away_from_box() {
+        let contact = circle_aabb_contact(
+            Vec2::new(13.0, 0.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::splat(10.0),
+        )
+        .unwrap();
+        assert_near(contact.normal, Vec2::X);
+        assert!((contact.depth - 2.0).abs() < 1e-4);
+        assert!(circle_aabb_contact(
+            Vec2::new(16.0, 0.0),
+            5.0,
+            Vec2::ZERO,
+            Vec2::splat(10.0),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn grazing_parallel_to_face_has_no_contact() {
+        // Sliding along the top face, touching it
+        assert!(sweep_circle(
+            Vec2::new(-100.0, 14.0),
+            Vec2::new(200.0, 0.0),
+            10.0,
+            Vec2::ZERO,
+            Vec2::new(50.0, 4.0),
+        )
+        .is_none());
+        // And just clear of it
+        assert!(sweep_circle(
+            Vec2::new(-100.0, 14.5),
+            Vec2::new(200.0, 0.0),
+            10.0,
+            Vec2::ZERO,
+            Vec2::new(50.0, 4.0),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn rays_miss_what_they_move_away_from() {
+        assert!(ray_aabb(
+            Vec2::new(0.0, 20.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::splat(-10.0),
+            Vec2::splat(10.0),
+        )
+        .is_none());
+        assert!(ray_circle(
+            Vec2::new(0.0, 20.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::ZERO,
+            5.0,
+        )
+        .is_none());
+
+        let time = ray_circle(
+            Vec2::new(0.0, 20.0),
+            Vec2::new(0.0, -20.0),
+            Vec2::ZERO,
+            5.0,
+        )
+        .unwrap();
+        assert!((time - 0.75).abs() < 1e-4);
     }
 }
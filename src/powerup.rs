@@ -0,0 +1,507 @@
+use std::f32::consts::FRAC_PI_6;
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    components::{
+        ball::{
+            Ball, BallAssets, BallBundle, BallSpeed, Serving,
+            StuckToPaddle,
+        },
+        paddle::Paddle,
+    },
+    config::GameConfig,
+    game::{
+        GameRng, GameplayStage, InGame, LifeLostEvent, Lives,
+    },
+    physics::{BrickDestroyedEvent, Velocity},
+};
+
+/// Drops power-ups from destroyed bricks and applies their effects.
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveEffects>()
+            .add_event::<PowerUpCollectedEvent>()
+            .add_event::<PowerUpExpiredEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (update_paddle_width, update_ball_speed)
+                        .chain()
+                        .in_set(GameplayStage::Effects),
+                    (
+                        spawn_power_ups,
+                        collect_power_ups,
+                        apply_power_ups,
+                        tick_power_ups,
+                        release_balls_on_sticky_expired,
+                        clear_power_ups_on_life_lost,
+                    )
+                        .chain()
+                        .in_set(GameplayStage::PowerUps),
+                ),
+            )
+            .add_systems(Update, update_ball_material);
+    }
+}
+
+/// Probability of a destroyed brick dropping a power-up.
+pub const POWER_UP_DROP_CHANCE: f64 = 0.2;
+pub const POWER_UP_SIZE: Vec2 = Vec2::new(40.0, 16.0);
+pub const POWER_UP_FALL_SPEED: f32 = 200.0;
+// Seconds a timed effect lasts after its last pick-up
+pub const POWER_UP_DURATION: f32 = 10.0;
+pub const WIDE_PADDLE_FACTOR: f32 = 1.5;
+pub const MAX_WIDE_PADDLE_STACKS: u32 = 2;
+pub const SLOW_BALL_FACTOR: f32 = 0.6;
+pub const MAX_LIVES: usize = 5;
+pub const MAX_BALLS: usize = 12;
+// Angle between a ball and each of the balls split off from it
+pub const MULTIBALL_SPREAD_ANGLE: f32 = FRAC_PI_6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUpKind {
+    WidePaddle,
+    SlowBall,
+    StickyPaddle,
+    ExtraLife,
+    MultiBall,
+    Fireball,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::WidePaddle,
+        PowerUpKind::SlowBall,
+        PowerUpKind::StickyPaddle,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::MultiBall,
+        PowerUpKind::Fireball,
+    ];
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::WidePaddle => Color::BLUE,
+            PowerUpKind::SlowBall => Color::CYAN,
+            PowerUpKind::StickyPaddle => Color::GREEN,
+            PowerUpKind::ExtraLife => Color::PINK,
+            PowerUpKind::MultiBall => Color::PURPLE,
+            PowerUpKind::Fireball => Color::ORANGE_RED,
+        }
+    }
+}
+
+/// A falling capsule the paddle can catch.
+#[derive(Component)]
+pub struct PowerUp(pub PowerUpKind);
+
+/// Sent when the paddle catches a power-up.
+#[derive(Event)]
+pub struct PowerUpCollectedEvent(pub PowerUpKind);
+
+/// Sent when a timed effect runs out.
+#[derive(Event)]
+pub struct PowerUpExpiredEvent(pub PowerUpKind);
+
+#[derive(Debug)]
+pub struct ActiveEffect {
+    pub timer: Timer,
+    pub stacks: u32,
+}
+
+/// Timed effects currently applied. Catching an active effect again
+/// restarts its timer; only the wide paddle also stacks, up to
+/// [`MAX_WIDE_PADDLE_STACKS`].
+#[derive(Resource, Default, Debug)]
+pub struct ActiveEffects(pub HashMap<PowerUpKind, ActiveEffect>);
+
+impl ActiveEffects {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.0.contains_key(&kind)
+    }
+
+    pub fn stacks(&self, kind: PowerUpKind) -> u32 {
+        self.0.get(&kind).map_or(0, |effect| effect.stacks)
+    }
+
+    pub fn paddle_width(&self, config: &GameConfig) -> f32 {
+        config.paddle_size.x
+            * WIDE_PADDLE_FACTOR
+                .powi(self.stacks(PowerUpKind::WidePaddle) as i32)
+    }
+
+    pub fn ball_speed_factor(&self) -> f32 {
+        if self.is_active(PowerUpKind::SlowBall) {
+            SLOW_BALL_FACTOR
+        } else {
+            1.0
+        }
+    }
+
+    fn add(&mut self, kind: PowerUpKind) {
+        let max_stacks = if kind == PowerUpKind::WidePaddle {
+            MAX_WIDE_PADDLE_STACKS
+        } else {
+            1
+        };
+        let effect =
+            self.0.entry(kind).or_insert(ActiveEffect {
+                timer: Timer::from_seconds(
+                    POWER_UP_DURATION,
+                    TimerMode::Once,
+                ),
+                stacks: 0,
+            });
+        effect.timer.reset();
+        effect.stacks = (effect.stacks + 1).min(max_stacks);
+    }
+}
+
+pub fn spawn_power_ups(
+    mut commands: Commands,
+    mut brick_destroyed_events: EventReader<BrickDestroyedEvent>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in brick_destroyed_events.iter() {
+        if !rng.gen_bool(POWER_UP_DROP_CHANCE) {
+            continue;
+        }
+        let kind = *PowerUpKind::ALL.choose(&mut **rng).unwrap();
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: kind.color(),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: event.position.extend(0.5),
+                    scale: POWER_UP_SIZE.extend(1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            PowerUp(kind),
+            Velocity(Vec2::new(0.0, -POWER_UP_FALL_SPEED)),
+            InGame,
+        ));
+    }
+}
+
+pub fn collect_power_ups(
+    mut commands: Commands,
+    power_up_query: Query<(Entity, &Transform, &PowerUp)>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    mut collected_events: EventWriter<PowerUpCollectedEvent>,
+    config: Res<GameConfig>,
+) {
+    let paddle_transform = paddle_query.single();
+
+    for (entity, transform, power_up) in &power_up_query {
+        let distance = (transform.translation
+            - paddle_transform.translation)
+            .truncate()
+            .abs();
+        let reach = (transform.scale + paddle_transform.scale)
+            .truncate()
+            / 2.0;
+
+        if distance.x <= reach.x && distance.y <= reach.y {
+            collected_events
+                .send(PowerUpCollectedEvent(power_up.0));
+            commands.entity(entity).despawn();
+        } else if transform.translation.y < config.bottom_wall()
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn apply_power_ups(
+    mut commands: Commands,
+    mut collected_events: EventReader<PowerUpCollectedEvent>,
+    mut effects: ResMut<ActiveEffects>,
+    mut lives: ResMut<Lives>,
+    ball_assets: Res<BallAssets>,
+    ball_query: Query<
+        (&Transform, &Velocity),
+        (With<Ball>, Without<Serving>),
+    >,
+) {
+    for event in collected_events.iter() {
+        match event.0 {
+            PowerUpKind::ExtraLife => {
+                lives.remaining =
+                    (lives.remaining + 1).min(MAX_LIVES);
+            }
+            PowerUpKind::MultiBall => {
+                // Every ball in play splits into three. Balls
+                // waiting to be served have no direction to split.
+                let mut ball_count = ball_query.iter().len();
+                for (transform, velocity) in &ball_query {
+                    for angle in [
+                        MULTIBALL_SPREAD_ANGLE,
+                        -MULTIBALL_SPREAD_ANGLE,
+                    ] {
+                        if ball_count >= MAX_BALLS {
+                            break;
+                        }
+                        commands.spawn((
+                            BallBundle::with_motion(
+                                &ball_assets,
+                                transform.translation,
+                                Vec2::from_angle(angle)
+                                    .rotate(velocity.0),
+                            ),
+                            InGame,
+                        ));
+                        ball_count += 1;
+                    }
+                }
+            }
+            // Everything else is timed
+            kind => effects.add(kind),
+        }
+    }
+}
+
+pub fn tick_power_ups(
+    mut effects: ResMut<ActiveEffects>,
+    mut expired_events: EventWriter<PowerUpExpiredEvent>,
+    time_step: Res<FixedTime>,
+) {
+    effects.0.retain(|&kind, effect| {
+        if effect.timer.tick(time_step.period).finished() {
+            expired_events.send(PowerUpExpiredEvent(kind));
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// Losing a life takes every active effect with it.
+pub fn clear_power_ups_on_life_lost(
+    mut life_lost_events: EventReader<LifeLostEvent>,
+    mut effects: ResMut<ActiveEffects>,
+) {
+    if !life_lost_events.is_empty() {
+        life_lost_events.clear();
+        effects.0.clear();
+    }
+}
+
+/// Releases every ball caught by the sticky paddle once it wears
+/// off, leaving any ball waiting to be served.
+pub fn release_balls_on_sticky_expired(
+    mut commands: Commands,
+    mut expired_events: EventReader<PowerUpExpiredEvent>,
+    stuck_query: Query<
+        Entity,
+        (With<StuckToPaddle>, Without<Serving>),
+    >,
+) {
+    if expired_events
+        .iter()
+        .any(|event| event.0 == PowerUpKind::StickyPaddle)
+    {
+        for entity in &stuck_query {
+            commands.entity(entity).remove::<StuckToPaddle>();
+        }
+    }
+}
+
+pub fn update_paddle_width(
+    effects: Res<ActiveEffects>,
+    config: Res<GameConfig>,
+    mut paddle_query: Query<&mut Transform, With<Paddle>>,
+) {
+    let mut paddle_transform = paddle_query.single_mut();
+    paddle_transform.scale.x = effects.paddle_width(&config);
+}
+
+/// Keeps every ball at the current level's speed, scaled by any
+/// active effect, without changing its direction.
+pub fn update_ball_speed(
+    effects: Res<ActiveEffects>,
+    ball_speed: Res<BallSpeed>,
+    mut ball_query: Query<&mut Velocity, With<Ball>>,
+) {
+    let speed = ball_speed.0 * effects.ball_speed_factor();
+    for mut velocity in &mut ball_query {
+        velocity.0 = velocity.normalize_or_zero() * speed;
+    }
+}
+
+/// Shows which balls are fireballs.
+pub fn update_ball_material(
+    effects: Res<ActiveEffects>,
+    ball_assets: Res<BallAssets>,
+    mut ball_query: Query<
+        &mut Handle<ColorMaterial>,
+        With<Ball>,
+    >,
+) {
+    let material = if effects.is_active(PowerUpKind::Fireball) {
+        &ball_assets.fireball_material
+    } else {
+        &ball_assets.material
+    };
+    for mut ball_material in &mut ball_query {
+        if *ball_material != *material {
+            *ball_material = material.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// An app running `system` every update, with a fixed step of
+    /// `step` seconds.
+    fn app_with<M>(
+        system: impl IntoSystemConfigs<M>,
+        step: f32,
+    ) -> App {
+        let mut app = App::new();
+        app.init_resource::<ActiveEffects>()
+            .add_event::<PowerUpCollectedEvent>()
+            .add_event::<PowerUpExpiredEvent>()
+            .insert_resource(FixedTime::new_from_secs(step))
+            .insert_resource(Lives { remaining: 1 })
+            .insert_resource(BallAssets {
+                size: Vec3::ONE,
+                mesh: Handle::default(),
+                material: Handle::default(),
+                fireball_material: Handle::default(),
+            })
+            .add_systems(Update, system);
+        app
+    }
+
+    fn effects(app: &App) -> &ActiveEffects {
+        app.world.resource::<ActiveEffects>()
+    }
+
+    #[test]
+    fn wide_paddle_stacks_up_to_its_limit() {
+        let config = GameConfig::default();
+        let mut effects = ActiveEffects::default();
+        effects.add(PowerUpKind::WidePaddle);
+        assert_eq!(effects.stacks(PowerUpKind::WidePaddle), 1);
+        for _ in 0..3 {
+            effects.add(PowerUpKind::WidePaddle);
+        }
+        assert_eq!(
+            effects.stacks(PowerUpKind::WidePaddle),
+            MAX_WIDE_PADDLE_STACKS
+        );
+        assert_eq!(
+            effects.paddle_width(&config),
+            config.paddle_size.x
+                * WIDE_PADDLE_FACTOR
+                    .powi(MAX_WIDE_PADDLE_STACKS as i32)
+        );
+    }
+
+    #[test]
+    fn other_effects_do_not_stack() {
+        let mut effects = ActiveEffects::default();
+        effects.add(PowerUpKind::SlowBall);
+        effects.add(PowerUpKind::SlowBall);
+        assert_eq!(effects.stacks(PowerUpKind::SlowBall), 1);
+        assert_eq!(
+            effects.ball_speed_factor(),
+            SLOW_BALL_FACTOR
+        );
+    }
+
+    #[test]
+    fn catching_an_effect_again_restarts_its_timer() {
+        let mut effects = ActiveEffects::default();
+        effects.add(PowerUpKind::SlowBall);
+        let effect =
+            effects.0.get_mut(&PowerUpKind::SlowBall).unwrap();
+        effect.timer.tick(Duration::from_secs_f32(
+            POWER_UP_DURATION * 0.8,
+        ));
+        effects.add(PowerUpKind::SlowBall);
+        assert_eq!(
+            effects.0[&PowerUpKind::SlowBall].timer.elapsed(),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn effects_expire_after_their_duration() {
+        let mut app =
+            app_with(tick_power_ups, POWER_UP_DURATION / 2.0);
+        app.world
+            .resource_mut::<ActiveEffects>()
+            .add(PowerUpKind::WidePaddle);
+
+        app.update();
+        assert!(effects(&app).is_active(PowerUpKind::WidePaddle));
+        app.update();
+        assert!(
+            !effects(&app).is_active(PowerUpKind::WidePaddle)
+        );
+        assert_eq!(
+            effects(&app).stacks(PowerUpKind::WidePaddle),
+            0
+        );
+        assert_eq!(
+            app.world
+                .resource::<Events<PowerUpExpiredEvent>>()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn multiball_stops_at_the_ball_limit() {
+        let mut app = app_with(apply_power_ups, 1.0 / 60.0);
+        app.world.spawn((
+            Transform::default(),
+            Velocity(Vec2::new(0.0, 400.0)),
+            Ball,
+        ));
+
+        let mut counts = Vec::new();
+        for _ in 0..3 {
+            app.world.send_event(PowerUpCollectedEvent(
+                PowerUpKind::MultiBall,
+            ));
+            app.update();
+            counts.push(
+                app.world
+                    .query_filtered::<(), With<Ball>>()
+                    .iter(&app.world)
+                    .count(),
+            );
+        }
+        assert_eq!(counts, [3, 9, MAX_BALLS]);
+    }
+
+    #[test]
+    fn extra_lives_stop_at_the_limit() {
+        let mut app = app_with(apply_power_ups, 1.0 / 60.0);
+        for _ in 0..MAX_LIVES + 2 {
+            app.world.send_event(PowerUpCollectedEvent(
+                PowerUpKind::ExtraLife,
+            ));
+        }
+        app.update();
+        assert_eq!(
+            app.world.resource::<Lives>().remaining,
+            MAX_LIVES
+        );
+    }
+}
@@ -0,0 +1,563 @@
+use std::{
+    fs,
+    hash::Hasher,
+    io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    components::{
+        ball::Ball,
+        brick::{Brick, Durability},
+        paddle::Paddle,
+    },
+    config::GameConfig,
+    game::{GameRng, Lives},
+    input::PaddleIntent,
+    level::CurrentLevel,
+    physics::Velocity,
+    ui::ScoreBoard,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not access the replay file: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse the replay: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write the replay: {0}")]
+    Write(#[from] ron::Error),
+    #[error(
+        "the replay was recorded with different settings, play it \
+         with the same config file and --set options"
+    )]
+    ConfigMismatch,
+}
+
+/// The same input held for a number of consecutive fixed steps.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize,
+)]
+pub struct InputRun {
+    pub steps: u32,
+    pub direction: f32,
+    #[serde(default)]
+    pub target: Option<f32>,
+    pub launch: bool,
+}
+
+/// Everything needed to play a game again exactly as it went, and
+/// what it should end up looking like.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub level: usize,
+    pub inputs: Vec<InputRun>,
+    pub score: usize,
+    pub hash: u64,
+    /// Hash of the settings the game was played with. Replays
+    /// recorded before it was kept don't have one.
+    #[serde(default)]
+    pub config_hash: Option<u64>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        let text = fs::read_to_string(path)?;
+        Ok(ron::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        fs::write(path, ron::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Checks that `config` plays the same as the settings the replay
+    /// was recorded with, as the game would end differently otherwise.
+    pub fn check_config(
+        &self,
+        config: &GameConfig,
+    ) -> Result<(), ReplayError> {
+        match self.config_hash {
+            Some(hash) if hash != config_hash(config) => {
+                Err(ReplayError::ConfigMismatch)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn push(&mut self, intent: &PaddleIntent) {
+        match self.inputs.last_mut() {
+            Some(run)
+                if run.direction == intent.direction
+                    && run.target == intent.target
+                    && run.launch == intent.launch =>
+            {
+                run.steps += 1;
+            }
+            _ => self.inputs.push(InputRun {
+                steps: 1,
+                direction: intent.direction,
+                target: intent.target,
+                launch: intent.launch,
+            }),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is
+/// guaranteed to give the same hash on every run and platform.
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Fnv1aHasher(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn write_vec2(hasher: &mut impl Hasher, v: Vec2) {
+    hasher.write_u32(v.x.to_bits());
+    hasher.write_u32(v.y.to_bits());
+}
+
+/// Hash of the settings that change how a game plays out. Colors,
+/// sounds and key bindings are left out, as the replay holds the
+/// inputs rather than the keys pressed.
+fn config_hash(config: &GameConfig) -> u64 {
+    let mut hasher = Fnv1aHasher::default();
+
+    for value in [
+        config.screen_width,
+        config.screen_height,
+        config.wall_thickness,
+        config.paddle_speed,
+        config.paddle_padding,
+        config.gap_between_paddle_and_floor,
+        config.ball_radius,
+        config.ball_speed,
+        config.ball_speed_increase_per_level,
+        config.auto_launch_delay,
+        config.gap_between_bricks,
+        config.gap_between_bricks_and_ceiling,
+        config.gap_between_bricks_and_sides,
+        config.gap_between_paddle_and_bricks,
+        config.mouse_smoothing,
+    ] {
+        hasher.write_u32(value.to_bits());
+    }
+    write_vec2(&mut hasher, config.paddle_size);
+    write_vec2(&mut hasher, config.brick_size);
+
+    hasher.finish()
+}
+
+/// The parts of a game that the inputs decide, to tell whether two
+/// games ended the same way.
+#[derive(SystemParam)]
+pub struct GameSnapshot<'w, 's> {
+    scoreboard: Res<'w, ScoreBoard>,
+    lives: Res<'w, Lives>,
+    current_level: Option<Res<'w, CurrentLevel>>,
+    paddle_query:
+        Query<'w, 's, &'static Transform, With<Paddle>>,
+    ball_query: Query<
+        'w,
+        's,
+        (&'static Transform, &'static Velocity),
+        With<Ball>,
+    >,
+    brick_query:
+        Query<'w, 's, (&'static Transform, &'static Brick)>,
+}
+
+impl GameSnapshot<'_, '_> {
+    pub fn score(&self) -> usize {
+        self.scoreboard.score
+    }
+
+    /// Hash of the exact state of the game, down to the bits of every
+    /// position and velocity.
+    pub fn hash(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::default();
+
+        hasher.write_u64(self.scoreboard.score as u64);
+        hasher.write_u64(self.lives.remaining as u64);
+        if let Some(current_level) = &self.current_level {
+            hasher.write_u64(current_level.0 as u64);
+        }
+        for transform in &self.paddle_query {
+            write_vec2(
+                &mut hasher,
+                transform.translation.truncate(),
+            );
+            hasher.write_u32(transform.scale.x.to_bits());
+        }
+        for (transform, velocity) in &self.ball_query {
+            write_vec2(
+                &mut hasher,
+                transform.translation.truncate(),
+            );
+            write_vec2(&mut hasher, velocity.0);
+        }
+        for (transform, brick) in &self.brick_query {
+            write_vec2(
+                &mut hasher,
+                transform.translation.truncate(),
+            );
+            if let Durability::Breakable { hits_left, .. } =
+                brick.0.durability
+            {
+                hasher.write_u32(hits_left);
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+/// Records the inputs of every game, writing them to `path` when the
+/// game ends.
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Option<Replay>,
+    /// Set once the game has done something a replay can't
+    /// reproduce, until the next game.
+    stopped: bool,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        ReplayRecorder {
+            path,
+            replay: None,
+            stopped: false,
+        }
+    }
+
+    fn save(&mut self, snapshot: &GameSnapshot) {
+        let Some(mut replay) = self.replay.take() else {
+            return;
+        };
+
+        replay.score = snapshot.score();
+        replay.hash = snapshot.hash();
+        match replay.save(&self.path) {
+            Ok(()) => info!("Saved replay to {:?}", self.path),
+            Err(error) => {
+                error!(
+                    "Failed to save replay to {:?}: {error}",
+                    self.path
+                )
+            }
+        }
+    }
+}
+
+/// Feeds the inputs of a replay to the paddle in place of a player.
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    run: usize,
+    steps_in_run: u32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer {
+            replay,
+            run: 0,
+            steps_in_run: 0,
+        }
+    }
+
+    fn next_input(&mut self) -> Option<InputRun> {
+        let run = *self.replay.inputs.get(self.run)?;
+        self.steps_in_run += 1;
+        if self.steps_in_run >= run.steps {
+            self.run += 1;
+            self.steps_in_run = 0;
+        }
+        Some(run)
+    }
+}
+
+/// Records the input of every fixed step, starting a new replay on
+/// the first step of each game. Changing the settings mid-game
+/// throws the recording away, as no single config could play it back.
+pub fn record_input(
+    mut recorder: ResMut<ReplayRecorder>,
+    intent: Res<PaddleIntent>,
+    rng: Res<GameRng>,
+    current_level: Res<CurrentLevel>,
+    config: Res<GameConfig>,
+) {
+    if recorder.stopped {
+        return;
+    }
+    let config_hash = config_hash(&config);
+    let replay = recorder.replay.get_or_insert_with(|| Replay {
+        seed: rng.seed,
+        level: current_level.0,
+        config_hash: Some(config_hash),
+        ..default()
+    });
+    if replay.config_hash != Some(config_hash) {
+        warn!("Stopped recording, as the settings changed");
+        recorder.replay = None;
+        recorder.stopped = true;
+        return;
+    }
+    replay.push(&intent);
+}
+
+/// Saves the game being recorded, if there is one.
+pub fn finish_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    snapshot: GameSnapshot,
+) {
+    recorder.save(&snapshot);
+    recorder.stopped = false;
+}
+
+/// Saves the game being recorded as it is before a level restart,
+/// which replays can't reproduce, and records nothing more of it.
+pub fn stop_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    snapshot: GameSnapshot,
+) {
+    recorder.save(&snapshot);
+    recorder.stopped = true;
+}
+
+/// Refuses to play a replay recorded with other settings than the
+/// game has, once its config file has been applied.
+pub fn check_replay_config(
+    player: Res<ReplayPlayer>,
+    config: Res<GameConfig>,
+) {
+    if let Err(error) = player.replay.check_config(&config) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+pub fn play_replay(
+    mut player: ResMut<ReplayPlayer>,
+    mut intent: ResMut<PaddleIntent>,
+    snapshot: GameSnapshot,
+    config: Res<GameConfig>,
+    app_exit_events: EventWriter<AppExit>,
+) {
+    match player.next_input() {
+        Some(run) => {
+            intent.direction = run.direction;
+            intent.target = run.target;
+            intent.launch = run.launch;
+        }
+        // The recording was stopped before the game ended
+        None => {
+            verify(&player, &snapshot, &config, app_exit_events)
+        }
+    }
+}
+
+/// Checks the replay once the game it plays has ended.
+pub fn finish_replay(
+    player: Res<ReplayPlayer>,
+    snapshot: GameSnapshot,
+    config: Res<GameConfig>,
+    app_exit_events: EventWriter<AppExit>,
+) {
+    verify(&player, &snapshot, &config, app_exit_events);
+}
+
+fn verify(
+    player: &ReplayPlayer,
+    snapshot: &GameSnapshot,
+    config: &GameConfig,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    // The config file may have been reloaded while the replay played
+    if let Err(error) = player.replay.check_config(config) {
+        eprintln!("Replay can't be verified: {error}");
+        std::process::exit(1);
+    }
+
+    let expected = (player.replay.score, player.replay.hash);
+    let actual = (snapshot.score(), snapshot.hash());
+    let finished = player.run >= player.replay.inputs.len();
+
+    if finished && expected == actual {
+        println!(
+            "Replay verified: score {}, hash {:016x}",
+            actual.0, actual.1
+        );
+        app_exit_events.send(AppExit);
+    } else {
+        eprintln!(
+            "Replay diverged: expected score {}, hash {:016x}, \
+             got score {}, hash {:016x}",
+            expected.0, expected.1, actual.0, actual.1
+        );
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(direction: f32, launch: bool) -> PaddleIntent {
+        PaddleIntent {
+            direction,
+            target: None,
+            launch,
+        }
+    }
+
+    fn run(
+        steps: u32,
+        direction: f32,
+        launch: bool,
+    ) -> InputRun {
+        InputRun {
+            steps,
+            direction,
+            target: None,
+            launch,
+        }
+    }
+
+    #[test]
+    fn same_inputs_are_recorded_as_one_run() {
+        let mut replay = Replay::default();
+        for _ in 0..3 {
+            replay.push(&intent(0.0, true));
+        }
+        for _ in 0..2 {
+            replay.push(&intent(-1.0, false));
+        }
+        replay.push(&intent(1.0, false));
+        assert_eq!(
+            replay.inputs,
+            [
+                run(3, 0.0, true),
+                run(2, -1.0, false),
+                run(1, 1.0, false)
+            ]
+        );
+
+        // Pointing at a position is an input of its own
+        replay.push(&PaddleIntent {
+            target: Some(50.0),
+            ..intent(1.0, false)
+        });
+        assert_eq!(replay.inputs.len(), 4);
+    }
+
+    #[test]
+    fn runs_play_back_step_by_step_until_the_last_ends() {
+        let inputs =
+            vec![run(2, -1.0, false), run(1, 0.5, true)];
+        let mut player = ReplayPlayer::new(Replay {
+            inputs: inputs.clone(),
+            ..default()
+        });
+        let played: Vec<_> =
+            std::iter::from_fn(|| player.next_input()).collect();
+        assert_eq!(played, [inputs[0], inputs[0], inputs[1]]);
+        // The last run is finished as soon as its last step is played
+        assert_eq!(player.run, inputs.len());
+        assert_eq!(player.next_input(), None);
+    }
+
+    #[test]
+    fn recorded_inputs_play_back_as_they_were() {
+        let directions =
+            [0.0, 0.0, 1.0, 1.0, 1.0, -0.25, 0.0, 0.0, 0.0, 0.0];
+        let mut replay = Replay::default();
+        for direction in directions {
+            replay.push(&intent(direction, false));
+        }
+        let mut player = ReplayPlayer::new(replay);
+        let played: Vec<_> =
+            std::iter::from_fn(|| player.next_input())
+                .map(|run| run.direction)
+                .collect();
+        assert_eq!(played, directions);
+    }
+
+    #[test]
+    fn config_hash_only_changes_with_gameplay_settings() {
+        let config = GameConfig::default();
+        assert_eq!(
+            config_hash(&config),
+            config_hash(&config.clone())
+        );
+        // The hash is written to replay files, so it must not change
+        // between runs, builds or platforms
+        assert_eq!(config_hash(&config), 0x20fe_f5ec_d381_8d19);
+
+        let recolored = GameConfig {
+            ball_color: Color::RED,
+            ..config.clone()
+        };
+        assert_eq!(
+            config_hash(&recolored),
+            config_hash(&config)
+        );
+
+        let faster = GameConfig {
+            ball_speed: config.ball_speed + 1.0,
+            ..config.clone()
+        };
+        assert_ne!(config_hash(&faster), config_hash(&config));
+        let wider = GameConfig {
+            paddle_size: config.paddle_size + Vec2::X,
+            ..config.clone()
+        };
+        assert_ne!(config_hash(&wider), config_hash(&config));
+    }
+
+    #[test]
+    fn replay_refuses_other_settings() {
+        let config = GameConfig::default();
+        let replay = Replay {
+            config_hash: Some(config_hash(&config)),
+            ..default()
+        };
+        assert!(replay.check_config(&config).is_ok());
+
+        let other = GameConfig {
+            ball_speed: config.ball_speed * 2.0,
+            ..config
+        };
+        assert!(matches!(
+            replay.check_config(&other),
+            Err(ReplayError::ConfigMismatch)
+        ));
+        // Replays from before the hash was kept can't be checked
+        assert!(Replay::default().check_config(&other).is_ok());
+    }
+}
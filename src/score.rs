@@ -0,0 +1,441 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::brick::BrickHit,
+    config::GameConfig,
+    game::{
+        check_for_level_cleared, GameplayStage,
+        LevelClearedEvent, LifeLostEvent, Lives,
+    },
+    physics::{
+        BrickDestroyedEvent, ColliderKind, CollisionEvent,
+    },
+    ui::ScoreBoard,
+};
+
+// Score for each point a brick is worth, leaving room for the
+// multipliers to make a difference on cheap bricks
+pub const BRICK_POINTS_SCALE: usize = 10;
+// Multiplier added for each brick hit in a combo after the first
+pub const COMBO_MULTIPLIER_STEP: f32 = 0.25;
+pub const MAX_COMBO_MULTIPLIER: f32 = 4.0;
+// Seconds a level can take before clearing it fast stops paying off
+pub const PAR_TIME: f32 = 120.0;
+// Multiplier added for clearing a level instantly, shrinking to
+// nothing at the par time
+pub const MAX_TIME_MULTIPLIER_BONUS: f32 = 1.0;
+pub const LEVEL_CLEAR_BONUS: usize = 500;
+pub const LIFE_BONUS: usize = 250;
+
+/// Turns broken bricks and cleared levels into points, publishing a
+/// [`ScoreEvent`] for everything scored.
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScoreEvent>()
+            .init_resource::<LevelClock>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_level_clock,
+                    award_brick_points,
+                    reset_combo_on_life_lost,
+                    award_level_bonuses,
+                )
+                    .chain()
+                    .after(check_for_level_cleared)
+                    .in_set(GameplayStage::Rules),
+            );
+    }
+}
+
+/// Why points were scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreReason {
+    /// A brick was broken by the `combo`th brick hit in a row
+    /// without the ball touching the paddle.
+    Brick {
+        combo: u32,
+    },
+    LevelClear,
+    /// Lives left when a level was cleared.
+    LivesLeft(usize),
+}
+
+/// Sent for every points awarded, after they are added to the
+/// [`ScoreBoard`].
+#[derive(Event, Debug, Clone)]
+pub struct ScoreEvent {
+    pub reason: ScoreReason,
+    pub points: usize,
+    /// Everything the base points were multiplied by.
+    pub multiplier: f32,
+    /// Where the points were scored, if anywhere in particular.
+    pub position: Option<Vec2>,
+}
+
+/// Time spent on the current level, counted in fixed steps so that
+/// replays score the same.
+#[derive(Resource, Default)]
+pub struct LevelClock {
+    pub elapsed: f32,
+}
+
+impl LevelClock {
+    /// Multiplier for scoring now, which shrinks as the level drags
+    /// on until the par time is up.
+    pub fn multiplier(&self) -> f32 {
+        let time_left = (1.0 - self.elapsed / PAR_TIME).max(0.0);
+        1.0 + MAX_TIME_MULTIPLIER_BONUS * time_left
+    }
+}
+
+/// Multiplier for breaking a brick with the `combo`th hit in a row.
+pub fn combo_multiplier(combo: u32) -> f32 {
+    (1.0 + COMBO_MULTIPLIER_STEP
+        * combo.saturating_sub(1) as f32)
+        .min(MAX_COMBO_MULTIPLIER)
+}
+
+fn scaled(points: usize, multiplier: f32) -> usize {
+    (points as f32 * multiplier).round() as usize
+}
+
+pub fn tick_level_clock(
+    mut clock: ResMut<LevelClock>,
+    time_step: Res<FixedTime>,
+) {
+    clock.elapsed += time_step.period.as_secs_f32();
+}
+
+/// Counts every hit on a breakable brick towards the combo, and
+/// scores broken bricks multiplied by the combo, by how fast the ball
+/// was going compared to the first level and by the time left to
+/// clear the level. Touching the paddle ends the combo.
+pub fn award_brick_points(
+    mut scoreboard: ResMut<ScoreBoard>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut brick_destroyed_events: EventReader<BrickDestroyedEvent>,
+    mut score_events: EventWriter<ScoreEvent>,
+    clock: Res<LevelClock>,
+    config: Res<GameConfig>,
+) {
+    // Every broken brick is reported by both events, in the same
+    // order
+    let mut destroyed = brick_destroyed_events.iter();
+    for collision in collision_events.iter() {
+        let hit = match collision.kind {
+            ColliderKind::Paddle if collision.normal.y > 0.0 => {
+                scoreboard.combo = 0;
+                continue;
+            }
+            ColliderKind::Brick { hit, .. }
+                if hit != BrickHit::Deflected =>
+            {
+                hit
+            }
+            _ => continue,
+        };
+        scoreboard.combo += 1;
+        if hit != BrickHit::Destroyed {
+            continue;
+        }
+        let Some(event) = destroyed.next() else {
+            continue;
+        };
+
+        let multiplier = combo_multiplier(scoreboard.combo)
+            * (event.ball_speed / config.ball_speed)
+            * clock.multiplier();
+        let points = scaled(
+            event.points * BRICK_POINTS_SCALE,
+            multiplier,
+        );
+        scoreboard.score += points;
+        score_events.send(ScoreEvent {
+            reason: ScoreReason::Brick {
+                combo: scoreboard.combo,
+            },
+            points,
+            multiplier,
+            position: Some(event.position),
+        });
+    }
+}
+
+/// A lost ball never made it back to the paddle, but still ends the
+/// combo.
+pub fn reset_combo_on_life_lost(
+    mut scoreboard: ResMut<ScoreBoard>,
+    mut life_lost_events: EventReader<LifeLostEvent>,
+) {
+    if life_lost_events.iter().count() > 0 {
+        scoreboard.combo = 0;
+    }
+}
+
+/// Rewards clearing a level, the faster the better, and every life
+/// kept through it. The next level starts without a combo.
+pub fn award_level_bonuses(
+    mut scoreboard: ResMut<ScoreBoard>,
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    mut score_events: EventWriter<ScoreEvent>,
+    clock: Res<LevelClock>,
+    lives: Res<Lives>,
+) {
+    if level_cleared_events.iter().count() == 0 {
+        return;
+    }
+
+    let multiplier = clock.multiplier();
+    let bonuses = [
+        ScoreEvent {
+            reason: ScoreReason::LevelClear,
+            points: scaled(LEVEL_CLEAR_BONUS, multiplier),
+            multiplier,
+            position: None,
+        },
+        ScoreEvent {
+            reason: ScoreReason::LivesLeft(lives.remaining),
+            points: LIFE_BONUS * lives.remaining,
+            multiplier: 1.0,
+            position: None,
+        },
+    ];
+    for bonus in bonuses {
+        if bonus.points > 0 {
+            scoreboard.score += bonus.points;
+            score_events.send(bonus);
+        }
+    }
+    scoreboard.combo = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::brick::BrickType;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn combo_multiplier_grows_to_its_limit() {
+        assert_eq!(combo_multiplier(0), 1.0);
+        assert_eq!(combo_multiplier(1), 1.0);
+        assert_eq!(
+            combo_multiplier(2),
+            1.0 + COMBO_MULTIPLIER_STEP
+        );
+        assert_eq!(
+            combo_multiplier(5),
+            1.0 + 4.0 * COMBO_MULTIPLIER_STEP
+        );
+        assert_eq!(combo_multiplier(1000), MAX_COMBO_MULTIPLIER);
+    }
+
+    #[test]
+    fn time_multiplier_shrinks_until_par_time() {
+        let at = |elapsed| LevelClock { elapsed }.multiplier();
+        assert_eq!(at(0.0), 1.0 + MAX_TIME_MULTIPLIER_BONUS);
+        assert!(approx_eq(
+            at(PAR_TIME / 2.0),
+            1.0 + MAX_TIME_MULTIPLIER_BONUS / 2.0
+        ));
+        assert_eq!(at(PAR_TIME), 1.0);
+        assert_eq!(at(PAR_TIME * 3.0), 1.0);
+    }
+
+    #[test]
+    fn scaled_points_are_rounded() {
+        assert_eq!(scaled(10, 1.25), 13);
+        assert_eq!(scaled(10, 1.24), 12);
+        assert_eq!(scaled(500, 1.0), 500);
+    }
+
+    /// The scoring systems with the level clock at `elapsed`, fed by
+    /// events sent to the world rather than by play.
+    fn scoring_app(elapsed: f32, lives: usize) -> App {
+        let mut app = App::new();
+        app.init_resource::<ScoreBoard>()
+            .insert_resource(GameConfig::default())
+            .insert_resource(LevelClock { elapsed })
+            .insert_resource(Lives { remaining: lives })
+            .add_event::<CollisionEvent>()
+            .add_event::<BrickDestroyedEvent>()
+            .add_event::<LifeLostEvent>()
+            .add_event::<LevelClearedEvent>()
+            .add_event::<ScoreEvent>()
+            .add_systems(
+                Update,
+                (
+                    award_brick_points,
+                    reset_combo_on_life_lost,
+                    award_level_bonuses,
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    fn collision(
+        kind: ColliderKind,
+        normal: Vec2,
+    ) -> CollisionEvent {
+        CollisionEvent {
+            ball: Entity::PLACEHOLDER,
+            collider: Entity::PLACEHOLDER,
+            kind,
+            point: Vec2::ZERO,
+            normal,
+            impact_speed: 400.0,
+        }
+    }
+
+    /// Hits a brick worth `points` with a ball at `ball_speed`, as
+    /// the physics reports it.
+    fn hit_brick(
+        app: &mut App,
+        hit: BrickHit,
+        points: usize,
+        ball_speed: f32,
+    ) {
+        let brick_type =
+            BrickType::breakable('3', Color::WHITE, 3, points);
+        app.world.send_event(collision(
+            ColliderKind::Brick { brick_type, hit },
+            Vec2::NEG_Y,
+        ));
+        if hit == BrickHit::Destroyed {
+            app.world.send_event(BrickDestroyedEvent {
+                entity: Entity::PLACEHOLDER,
+                position: Vec2::new(10.0, 200.0),
+                points,
+                ball_speed,
+            });
+        }
+        app.update();
+    }
+
+    fn hit_paddle(app: &mut App, normal: Vec2) {
+        app.world
+            .send_event(collision(ColliderKind::Paddle, normal));
+        app.update();
+    }
+
+    fn scoreboard(app: &App) -> (usize, u32) {
+        let scoreboard = app.world.resource::<ScoreBoard>();
+        (scoreboard.score, scoreboard.combo)
+    }
+
+    fn score_events(app: &mut App) -> Vec<ScoreEvent> {
+        app.world
+            .resource_mut::<Events<ScoreEvent>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn damaging_a_brick_extends_the_combo_without_scoring() {
+        let mut app = scoring_app(PAR_TIME, 3);
+        let speed = GameConfig::default().ball_speed;
+
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+        assert_eq!(scoreboard(&app), (0, 1));
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+        assert_eq!(scoreboard(&app), (0, 2));
+        // Steel bricks neither score nor count
+        hit_brick(&mut app, BrickHit::Deflected, 0, speed);
+        assert_eq!(scoreboard(&app), (0, 2));
+        assert!(score_events(&mut app).is_empty());
+
+        hit_brick(&mut app, BrickHit::Destroyed, 5, speed);
+        let points =
+            scaled(5 * BRICK_POINTS_SCALE, combo_multiplier(3));
+        assert_eq!(scoreboard(&app), (points, 3));
+        let events = score_events(&mut app);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].reason,
+            ScoreReason::Brick { combo: 3 }
+        );
+        assert_eq!(events[0].points, points);
+        assert_eq!(
+            events[0].position,
+            Some(Vec2::new(10.0, 200.0))
+        );
+    }
+
+    #[test]
+    fn brick_points_are_multiplied_by_ball_speed_and_time() {
+        let mut app = scoring_app(0.0, 3);
+        let speed = GameConfig::default().ball_speed;
+        hit_brick(&mut app, BrickHit::Destroyed, 1, speed * 1.5);
+
+        let multiplier = 1.5 * (1.0 + MAX_TIME_MULTIPLIER_BONUS);
+        let events = score_events(&mut app);
+        assert!(approx_eq(events[0].multiplier, multiplier));
+        assert_eq!(
+            scoreboard(&app).0,
+            scaled(BRICK_POINTS_SCALE, multiplier)
+        );
+    }
+
+    #[test]
+    fn combo_ends_on_the_paddle_or_losing_the_ball() {
+        let mut app = scoring_app(PAR_TIME, 3);
+        let speed = GameConfig::default().ball_speed;
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+
+        // The paddle's underside is no bounce off its top
+        hit_paddle(&mut app, Vec2::NEG_Y);
+        assert_eq!(scoreboard(&app).1, 2);
+        hit_paddle(&mut app, Vec2::Y);
+        assert_eq!(scoreboard(&app).1, 0);
+
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+        assert_eq!(scoreboard(&app).1, 1);
+        app.world.send_event(LifeLostEvent);
+        app.update();
+        assert_eq!(scoreboard(&app).1, 0);
+    }
+
+    #[test]
+    fn clearing_a_level_pays_for_time_and_lives_left() {
+        let mut app = scoring_app(PAR_TIME / 2.0, 2);
+        let speed = GameConfig::default().ball_speed;
+        hit_brick(&mut app, BrickHit::Damaged, 5, speed);
+
+        // The combo doesn't carry over to the next level
+        app.world.send_event(LevelClearedEvent);
+        app.update();
+        let level_bonus = scaled(
+            LEVEL_CLEAR_BONUS,
+            1.0 + MAX_TIME_MULTIPLIER_BONUS / 2.0,
+        );
+        assert_eq!(
+            scoreboard(&app),
+            (level_bonus + 2 * LIFE_BONUS, 0)
+        );
+        let reasons: Vec<_> = score_events(&mut app)
+            .iter()
+            .map(|event| event.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [ScoreReason::LevelClear, ScoreReason::LivesLeft(2)]
+        );
+    }
+
+    #[test]
+    fn no_lives_left_pays_no_life_bonus() {
+        let mut app = scoring_app(PAR_TIME, 0);
+        app.world.send_event(LevelClearedEvent);
+        app.update();
+        assert_eq!(scoreboard(&app).0, LEVEL_CLEAR_BONUS);
+        assert_eq!(score_events(&mut app).len(), 1);
+    }
+}
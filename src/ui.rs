@@ -1,26 +1,32 @@
 use bevy::prelude::*;
 
+use crate::components::brick::update_brick_color;
+
 pub const TEXT_COLOR: Color = Color::WHITE;
 pub const SCORE_COLOR: Color = Color::WHITE;
 
-pub const SCOREBOARD_FONT_SIZE: f32 = 40.0;
-pub const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
-
 pub const BACKGROUND_COLOR: Color = Color::GOLD;
 pub const PADDLE_COLOR: Color = Color::WHITE;
 pub const WALL_COLOR: Color = Color::LIME_GREEN;
 pub const BALL_COLOR: Color = Color::WHITE;
-pub const BRICK_COLOR: Color = Color::WHITE;
+pub const FIREBALL_COLOR: Color = Color::ORANGE_RED;
 
-#[derive(Resource)]
-pub struct ScoreBoard {
-    pub score: usize,
+/// Keeps the score, and the bricks' colors up to date with the
+/// damage they take.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoreBoard>()
+            .add_systems(Update, update_brick_color);
+    }
 }
 
-pub fn update_scoreboard(
-    scoreboard: Res<ScoreBoard>,
-    mut query: Query<&mut Text>,
-) {
-    let mut text = query.single_mut();
-    text.sections[1].value = scoreboard.score.to_string();
+/// Points scored in the current game.
+#[derive(Resource, Default)]
+pub struct ScoreBoard {
+    pub score: usize,
+    /// Bricks hit in a row without the ball touching the paddle.
+    /// Steel bricks don't count.
+    pub combo: u32,
 }
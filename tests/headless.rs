@@ -0,0 +1,93 @@
+//! Runs the game binary without a window, as the bot and replays do.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// A folder of its own for each test, standing in for the user's data
+/// folder so that nothing saved there changes the game.
+fn temp_folder(test: &str) -> PathBuf {
+    let folder = env::temp_dir()
+        .join(format!("breakout-{test}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    folder
+}
+
+fn breakout(data_folder: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_breakout"))
+        .args(args)
+        // Where the assets folder is looked for
+        .env("CARGO_MANIFEST_DIR", env!("CARGO_MANIFEST_DIR"))
+        .env("XDG_DATA_HOME", data_folder)
+        .env("HOME", data_folder)
+        .env("APPDATA", data_folder)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "exited with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn headless_game_plays() {
+    let folder = temp_folder("headless");
+    let output = breakout(
+        &folder,
+        &["--headless", "--seed", "7", "--max-steps", "600"],
+    );
+    let stdout = stdout(&output);
+    assert!(stdout.contains("Level: 1"), "{stdout}");
+    assert!(stdout.contains("Steps: 600"), "{stdout}");
+    fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn recorded_game_replays_the_same() {
+    let folder = temp_folder("replay");
+    let replay = folder.join("game.replay.ron");
+    let replay = replay.to_str().unwrap();
+
+    let played = stdout(&breakout(
+        &folder,
+        &["--headless", "--seed", "7", "--record", replay],
+    ));
+    let score = played
+        .lines()
+        .find_map(|line| line.strip_prefix("Score: "))
+        .unwrap();
+
+    let replayed = stdout(&breakout(
+        &folder,
+        &["--headless", "--replay", replay],
+    ));
+    assert!(
+        replayed.contains(&format!(
+            "Replay verified: score {score},"
+        )),
+        "{replayed}"
+    );
+
+    // Other settings would play a different game
+    let output = breakout(
+        &folder,
+        &[
+            "--headless",
+            "--replay",
+            replay,
+            "--set",
+            "ball_speed=650",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_dir_all(folder).unwrap();
+}